@@ -1,137 +1,265 @@
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 
-struct BoardingPass {
-    row: String,
-    column: String,
-    row_nr: Option<u32>,
-    column_nr: Option<u32>,
+const ROW_LENGTH: usize = 7;
+const COLUMN_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Seat {
+    pub row: u32,
+    pub column: u32,
 }
 
-impl BoardingPass {
-    fn new(row: String, column: String) -> BoardingPass {
-        BoardingPass {
-            row,
-            column,
-            row_nr: None,
-            column_nr: None,
-        }
+impl Seat {
+    pub fn new(row: u32, column: u32) -> Seat {
+        Seat { row, column }
     }
 
-    fn id(self) -> u32 {
-        let row_nr = self.row_nr.unwrap();
-        let column_nr = self.column_nr.unwrap();
-        row_nr * 8 + column_nr
+    pub fn id(&self) -> u32 {
+        self.row * 8 + self.column
     }
 }
 
-fn read_boarding_pass(text: &str) -> BoardingPass {
-    BoardingPass::new(text[..7].to_string(), text[7..].to_string())
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseSeatError {
+    WrongLength(usize),
+    InvalidCharacter { position: usize, character: char },
+}
+
+impl fmt::Display for ParseSeatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseSeatError::WrongLength(length) => write!(
+                f,
+                "expected {} characters, found {}",
+                ROW_LENGTH + COLUMN_LENGTH,
+                length
+            ),
+            ParseSeatError::InvalidCharacter {
+                position,
+                character,
+            } => {
+                let expected = if *position < ROW_LENGTH {
+                    "'F' or 'B'"
+                } else {
+                    "'L' or 'R'"
+                };
+                write!(
+                    f,
+                    "unexpected character {:?} at position {}, expected {}",
+                    character,
+                    position + 1,
+                    expected
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseSeatError {}
+
+impl FromStr for Seat {
+    type Err = ParseSeatError;
+
+    fn from_str(text: &str) -> Result<Seat, ParseSeatError> {
+        let characters = text.chars().collect::<Vec<char>>();
+        if characters.len() != ROW_LENGTH + COLUMN_LENGTH {
+            return Err(ParseSeatError::WrongLength(characters.len()));
+        }
+        let row =
+            generic_binary_parse(&characters[..ROW_LENGTH], 0, |character| match character {
+                'F' => Some(0),
+                'B' => Some(1),
+                _ => None,
+            })?;
+        let column =
+            generic_binary_parse(
+                &characters[ROW_LENGTH..],
+                ROW_LENGTH,
+                |character| match character {
+                    'L' => Some(0),
+                    'R' => Some(1),
+                    _ => None,
+                },
+            )?;
+        Ok(Seat { row, column })
+    }
 }
 
-fn parse_boarding_pass(pass: &mut BoardingPass) {
-    let row_nr = generic_binary_parse(&pass.row, |byte| match byte {
-        b'F' => 0,
-        b'B' => 1,
-        _ => panic!(),
-    });
-    pass.row_nr = Some(row_nr);
-    let column_nr = generic_binary_parse(&pass.column, |byte| match byte {
-        b'L' => 0,
-        b'R' => 1,
-        _ => panic!(),
-    });
-    pass.column_nr = Some(column_nr);
+impl fmt::Display for Seat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let row = generic_binary_format(self.row, ROW_LENGTH, 'F', 'B');
+        let column = generic_binary_format(self.column, COLUMN_LENGTH, 'L', 'R');
+        write!(f, "{}{}", row, column)
+    }
 }
 
-fn generic_binary_parse<F>(text: &str, f: F) -> u32
+fn generic_binary_parse<F>(characters: &[char], offset: usize, f: F) -> Result<u32, ParseSeatError>
 where
-    F: Fn(&u8) -> u32,
+    F: Fn(char) -> Option<u32>,
 {
-    text.as_bytes()
+    characters
         .iter()
-        .rev()
         .enumerate()
-        .fold(0, |acc, (i, byte)| acc + f(byte) * 2u32.pow(i as u32))
+        .try_fold(0, |acc, (i, &character)| match f(character) {
+            Some(bit) => Ok(acc * 2 + bit),
+            None => Err(ParseSeatError::InvalidCharacter {
+                position: offset + i,
+                character,
+            }),
+        })
 }
 
-fn get_max(text: &str) -> u32 {
-    text.lines()
-        .map(|row| {
-            let mut pass = read_boarding_pass(row);
-            parse_boarding_pass(&mut pass);
-            pass
-        })
-        .fold(0, |acc, pass| {
-            let id = pass.id();
-            if id > acc {
-                id
-            } else {
-                acc
-            }
-        })
+fn generic_binary_format(value: u32, length: usize, zero: char, one: char) -> String {
+    (0..length)
+        .rev()
+        .map(|i| if value >> i & 1 == 1 { one } else { zero })
+        .collect()
 }
 
-pub fn get_max_pass_id(filename: &str) -> u32 {
-    let text = fs::read_to_string(filename).unwrap();
-    get_max(&text)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSeatsError {
+    pub errors: Vec<(usize, ParseSeatError)>,
 }
 
-pub fn get_missing_seat(filename: &str) -> u32 {
-    let text = fs::read_to_string(filename).unwrap();
-    let mut sorted_passes = text
-        .lines()
-        .map(|row| {
-            let mut pass = read_boarding_pass(row);
-            parse_boarding_pass(&mut pass);
-            pass.id()
-        })
-        .collect::<Vec<u32>>();
+impl fmt::Display for ParseSeatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines = self
+            .errors
+            .iter()
+            .map(|(line, error)| format!("line {}: {}", line, error))
+            .collect::<Vec<String>>();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+impl std::error::Error for ParseSeatsError {}
+
+pub fn parse_seats(text: &str) -> Result<Vec<Seat>, ParseSeatsError> {
+    let mut seats = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        match line.trim().parse::<Seat>() {
+            Ok(seat) => seats.push(seat),
+            Err(error) => errors.push((i + 1, error)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(seats)
+    } else {
+        Err(ParseSeatsError { errors })
+    }
+}
+
+fn get_max(seats: &[Seat]) -> u32 {
+    seats.iter().map(|seat| seat.id()).max().unwrap_or(0)
+}
+
+fn get_missing(seats: &[Seat]) -> u32 {
+    let mut sorted_passes = seats.iter().map(|seat| seat.id()).collect::<Vec<u32>>();
     sorted_passes.sort_unstable();
-    let mut last_id = *sorted_passes.first().unwrap();
     let mut missing_id = 0;
-    for id in sorted_passes.into_iter().skip(1) {
-        if id == last_id + 2 {
-            missing_id = id - 1;
+    for pair in sorted_passes.windows(2) {
+        if pair[1] == pair[0] + 2 {
+            missing_id = pair[0] + 1;
             break;
         }
-        last_id = id;
     }
     missing_id
 }
 
+pub fn get_max_pass_id(filename: &str) -> Result<u32, ParseSeatsError> {
+    let text = fs::read_to_string(filename).unwrap();
+    let seats = parse_seats(&text)?;
+    Ok(get_max(&seats))
+}
+
+pub fn get_missing_seat(filename: &str) -> Result<u32, ParseSeatsError> {
+    let text = fs::read_to_string(filename).unwrap();
+    let seats = parse_seats(&text)?;
+    Ok(get_missing(&seats))
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{get_max_pass_id, parse_boarding_pass, read_boarding_pass, get_missing_seat};
+    use crate::{get_max_pass_id, get_missing_seat, parse_seats, ParseSeatError, Seat};
 
     #[test]
-    fn test_split() {
-        let text = "FBFBBFFRLR";
-        let pass = read_boarding_pass(text);
-        assert_eq!("FBFBBFF".to_string(), pass.row);
-        assert_eq!("RLR".to_string(), pass.column);
+    fn test_parse() {
+        let seat = "FBFBBFFRLR".parse::<Seat>().unwrap();
+        assert_eq!(Seat::new(44, 5), seat);
+        assert_eq!(357, seat.id());
     }
 
     #[test]
-    fn test_parse() {
-        let text = "FBFBBFFRLR";
-        let mut pass = read_boarding_pass(text);
-        parse_boarding_pass(&mut pass);
-        assert_eq!(Some(44), pass.row_nr);
-        assert_eq!(Some(5), pass.column_nr);
+    fn test_display() {
+        assert_eq!("FBFBBFFRLR", Seat::new(44, 5).to_string());
+        assert_eq!(
+            "BBFFBBFRLL",
+            "BBFFBBFRLL".parse::<Seat>().unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn test_ord() {
+        let mut seats = vec![Seat::new(70, 7), Seat::new(14, 7), Seat::new(70, 2)];
+        seats.sort();
+        assert_eq!(
+            vec![Seat::new(14, 7), Seat::new(70, 2), Seat::new(70, 7)],
+            seats
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Err(ParseSeatError::WrongLength(3)), "FBF".parse::<Seat>());
+        assert_eq!(
+            Err(ParseSeatError::InvalidCharacter {
+                position: 2,
+                character: 'X'
+            }),
+            "FBXBBFFRLR".parse::<Seat>()
+        );
+        assert_eq!(
+            Err(ParseSeatError::InvalidCharacter {
+                position: 7,
+                character: 'F'
+            }),
+            "FBFBBFFFLR".parse::<Seat>()
+        );
+    }
+
+    #[test]
+    fn test_parse_seats_reports_all_lines() {
+        let error = parse_seats("FBFBBFFRLR\nFBF\nBFFFBBFRRR\nFBFBBFFRLX\n").unwrap_err();
+        assert_eq!(
+            vec![2, 4],
+            error
+                .errors
+                .iter()
+                .map(|(line, _)| *line)
+                .collect::<Vec<usize>>()
+        );
+        assert_eq!(
+            "line 2: expected 10 characters, found 3\n\
+             line 4: unexpected character 'X' at position 10, expected 'L' or 'R'",
+            error.to_string()
+        );
     }
 
     #[test]
     fn test_max() {
-        assert_eq!(820, get_max_pass_id("data/example.txt"));
+        assert_eq!(Ok(820), get_max_pass_id("data/example.txt"));
     }
 
     #[test]
     fn test_task1() {
-        assert_eq!(861, get_max_pass_id("data/boarding_pass.txt"));
+        assert_eq!(Ok(861), get_max_pass_id("data/boarding_pass.txt"));
     }
 
     #[test]
     fn test_task2() {
-        assert_eq!(633, get_missing_seat("data/boarding_pass.txt"));
+        assert_eq!(Ok(633), get_missing_seat("data/boarding_pass.txt"));
     }
 }
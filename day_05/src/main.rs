@@ -1,7 +1,11 @@
 fn main() {
-    let max = day_05::get_max_pass_id("data/boarding_pass.txt");
-    println!("Maximal boarding pass number is: {}", max);
+    match day_05::get_max_pass_id("data/boarding_pass.txt") {
+        Ok(max) => println!("Maximal boarding pass number is: {}", max),
+        Err(error) => eprintln!("Invalid boarding passes:\n{}", error),
+    }
 
-    let missing_seat = day_05::get_missing_seat("data/boarding_pass.txt");
-    println!("Missing id is: {}", missing_seat);
+    match day_05::get_missing_seat("data/boarding_pass.txt") {
+        Ok(missing_seat) => println!("Missing id is: {}", missing_seat),
+        Err(error) => eprintln!("Invalid boarding passes:\n{}", error),
+    }
 }
@@ -1,77 +1,549 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
 
-fn get_group_answers(text: &str) -> HashSet<String> {
-    let re = regex::Regex::new(r"[a-z]").unwrap();
-    let mut answers = HashSet::new();
-    for cap in re.captures_iter(text) {
-        answers.insert(cap[0].to_string());
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quorum {
+    AtLeast(usize),
+    AtLeastPercent(u32),
+    Exactly(usize),
+}
+
+impl Quorum {
+    pub const ANYONE: Quorum = Quorum::AtLeast(1);
+    pub const EVERYONE: Quorum = Quorum::AtLeastPercent(100);
+
+    fn is_met(&self, count: usize, group_size: usize) -> bool {
+        match *self {
+            Quorum::AtLeast(k) => count >= k,
+            Quorum::AtLeastPercent(p) => count * 100 >= p as usize * group_size,
+            Quorum::Exactly(k) => count == k,
+        }
     }
-    answers
 }
 
-fn get_flight_answers(text: &str) -> Vec<HashSet<String>> {
-    get_flight_answers_generic(text, |group| get_group_answers(group))
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumCount {
+    pub groups: Vec<u32>,
+    pub total: u32,
 }
 
-fn get_flight_answers_generic<F>(text: &str, f: F) -> Vec<HashSet<String>>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AnswerSet<const W: usize = 1> {
+    words: [u64; W],
+}
+
+impl<const W: usize> AnswerSet<W> {
+    pub const CAPACITY: usize = 64 * W;
+
+    pub fn new() -> AnswerSet<W> {
+        AnswerSet { words: [0; W] }
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        assert!(
+            index < Self::CAPACITY,
+            "answer {} does not fit in {} bits",
+            index,
+            Self::CAPACITY
+        );
+        self.words[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        index < Self::CAPACITY && self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn union(&self, other: &AnswerSet<W>) -> AnswerSet<W> {
+        let mut words = self.words;
+        for (word, other) in words.iter_mut().zip(other.words.iter()) {
+            *word |= other;
+        }
+        AnswerSet { words }
+    }
+
+    pub fn intersection(&self, other: &AnswerSet<W>) -> AnswerSet<W> {
+        let mut words = self.words;
+        for (word, other) in words.iter_mut().zip(other.words.iter()) {
+            *word &= other;
+        }
+        AnswerSet { words }
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(i, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+impl<const W: usize> Default for AnswerSet<W> {
+    fn default() -> AnswerSet<W> {
+        AnswerSet::new()
+    }
+}
+
+impl<const W: usize> std::iter::FromIterator<usize> for AnswerSet<W> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> AnswerSet<W> {
+        let mut set = AnswerSet::new();
+        for index in iter {
+            set.insert(index);
+        }
+        set
+    }
+}
+
+fn get_person_answers(line: &str) -> AnswerSet {
+    line.bytes()
+        .filter(|byte| byte.is_ascii_lowercase())
+        .map(|byte| (byte - b'a') as usize)
+        .collect()
+}
+
+fn get_group_people(text: &str) -> impl Iterator<Item = AnswerSet> + '_ {
+    text.lines()
+        .map(get_person_answers)
+        .filter(|person| !person.is_empty())
+}
+
+fn tally_people<I, const W: usize>(people: I) -> (usize, Vec<usize>)
 where
-    F: Fn(&str) -> HashSet<String>
+    I: Iterator<Item = AnswerSet<W>>,
 {
-    let re = regex::Regex::new(r"(?m)^\W*$").unwrap();
-    re.split(text).map(|group| f(group)).collect()
+    let mut group_size = 0;
+    let mut tally = vec![0; AnswerSet::<W>::CAPACITY];
+    for person in people {
+        group_size += 1;
+        for answer in person.iter() {
+            tally[answer] += 1;
+        }
+    }
+    (group_size, tally)
 }
 
-pub fn count_flight_answers(filename: &str) -> u32 {
+fn apply_quorum<I, const W: usize>(people: I, quorum: Quorum) -> AnswerSet<W>
+where
+    I: Iterator<Item = AnswerSet<W>>,
+{
+    if quorum == Quorum::ANYONE {
+        return people.fold(AnswerSet::new(), |acc, person| acc.union(&person));
+    }
+    if quorum == Quorum::EVERYONE {
+        return people
+            .reduce(|acc, person| acc.intersection(&person))
+            .unwrap_or_default();
+    }
+    let (group_size, tally) = tally_people(people);
+    (0..tally.len())
+        .filter(|&answer| tally[answer] > 0 && quorum.is_met(tally[answer], group_size))
+        .collect()
+}
+
+fn get_group_tally(text: &str) -> (usize, Vec<usize>) {
+    tally_people(get_group_people(text))
+}
+
+fn get_group_quorum(text: &str, quorum: Quorum) -> AnswerSet {
+    apply_quorum(get_group_people(text), quorum)
+}
+
+fn get_flight_answers_quorum(text: &str, quorum: Quorum) -> Vec<AnswerSet> {
+    get_flight_answers_generic(text, |group| get_group_quorum(group, quorum))
+}
+
+fn get_flight_answers_generic<T, F>(text: &str, f: F) -> Vec<T>
+where
+    F: FnMut(&str) -> T,
+{
+    split_groups(text, r"(?m)^\W*$", f)
+}
+
+fn split_groups<T, F>(text: &str, separator: &str, f: F) -> Vec<T>
+where
+    F: FnMut(&str) -> T,
+{
+    let re = regex::Regex::new(separator).unwrap();
+    re.split(text).map(f).collect()
+}
+
+fn count_quorum(text: &str, quorum: Quorum) -> QuorumCount {
+    let groups: Vec<u32> = get_flight_answers_quorum(text, quorum)
+        .iter()
+        .map(|set| set.len() as u32)
+        .collect();
+    let total = groups.iter().sum();
+    QuorumCount { groups, total }
+}
+
+pub fn count_flight_answers_quorum(filename: &str, quorum: Quorum) -> QuorumCount {
     let text = fs::read_to_string(filename).unwrap();
-    get_flight_answers(&text).iter().fold(0, |acc, set| acc + set.len() as u32)
+    count_quorum(&text, quorum)
+}
+
+pub fn count_flight_answers(filename: &str) -> u32 {
+    count_flight_answers_quorum(filename, Quorum::ANYONE).total
+}
+
+pub fn count_flight_answers_all(filename: &str) -> u32 {
+    count_flight_answers_quorum(filename, Quorum::EVERYONE).total
+}
+
+const QUESTION_WORDS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tokenizer {
+    Alphabet(Vec<char>),
+    Delimited {
+        delimiters: Vec<char>,
+        word_chars: Vec<char>,
+    },
+}
+
+impl Tokenizer {
+    pub fn lowercase() -> Tokenizer {
+        Tokenizer::Alphabet(('a'..='z').collect())
+    }
+
+    // Tokens are runs of alphanumeric characters plus `word_chars`; this
+    // constructor allows '_' inside tokens, as identifiers usually do.
+    pub fn delimited(delimiters: &[char]) -> Tokenizer {
+        Tokenizer::Delimited {
+            delimiters: delimiters.to_vec(),
+            word_chars: vec!['_'],
+        }
+    }
+
+    fn tokens<'a>(&self, line: &'a str) -> (Vec<&'a str>, Vec<(usize, char)>) {
+        let mut tokens = Vec::new();
+        let mut unrecognised = Vec::new();
+        let mut token_start = None;
+        for (column, (i, character)) in line.char_indices().enumerate() {
+            match self {
+                Tokenizer::Alphabet(alphabet) => {
+                    if alphabet.contains(&character) {
+                        tokens.push(&line[i..i + character.len_utf8()]);
+                    } else if !character.is_whitespace() {
+                        unrecognised.push((column + 1, character));
+                    }
+                }
+                Tokenizer::Delimited {
+                    delimiters,
+                    word_chars,
+                } => {
+                    if character.is_alphanumeric() || word_chars.contains(&character) {
+                        token_start.get_or_insert(i);
+                        continue;
+                    }
+                    if let Some(start) = token_start.take() {
+                        tokens.push(&line[start..i]);
+                    }
+                    if !delimiters.contains(&character) && !character.is_whitespace() {
+                        unrecognised.push((column + 1, character));
+                    }
+                }
+            }
+        }
+        if let Some(start) = token_start {
+            tokens.push(&line[start..]);
+        }
+        (tokens, unrecognised)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnrecognisedCharacter {
+    pub line: usize,
+    pub column: usize,
+    pub character: char,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenizeError {
+    Unrecognised(Vec<UnrecognisedCharacter>),
+    TooManyQuestions(usize),
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenizeError::Unrecognised(characters) => {
+                let characters = characters
+                    .iter()
+                    .map(|c| {
+                        format!("{:?} at line {}, column {}", c.character, c.line, c.column)
+                    })
+                    .collect::<Vec<String>>();
+                write!(f, "unrecognised characters: {}", characters.join(", "))
+            }
+            TokenizeError::TooManyQuestions(count) => write!(
+                f,
+                "{} distinct questions found, at most {} are supported",
+                count,
+                AnswerSet::<QUESTION_WORDS>::CAPACITY
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TokenizeError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenizedCount {
+    pub questions: Vec<String>,
+    pub count: QuorumCount,
+}
+
+struct QuestionTable {
+    names: Vec<String>,
+    indices: HashMap<String, usize>,
 }
 
-fn get_group_all(text: &str) -> HashSet<String> {
-    let re = regex::Regex::new(r"[a-z]").unwrap();
-    let answers: Vec<HashSet<String>> = text.lines().map(|line| {
-        re.captures_iter(line).fold(HashSet::new(), |mut acc, cap| {
-            acc.insert(cap[0].to_string());
-            acc
+impl QuestionTable {
+    fn new(tokenizer: &Tokenizer) -> QuestionTable {
+        let mut table = QuestionTable {
+            names: Vec::new(),
+            indices: HashMap::new(),
+        };
+        if let Tokenizer::Alphabet(alphabet) = tokenizer {
+            for character in alphabet {
+                table.index(&character.to_string());
+            }
+        }
+        table
+    }
+
+    fn index(&mut self, token: &str) -> usize {
+        if let Some(&index) = self.indices.get(token) {
+            return index;
+        }
+        let index = self.names.len();
+        self.names.push(token.to_string());
+        self.indices.insert(token.to_string(), index);
+        index
+    }
+}
+
+fn count_tokenized(
+    text: &str,
+    tokenizer: &Tokenizer,
+    quorum: Quorum,
+) -> Result<TokenizedCount, TokenizeError> {
+    let mut table = QuestionTable::new(tokenizer);
+    let mut unrecognised = Vec::new();
+    let mut line_offset = (0, 0);
+    // Only whitespace separates groups here: a line of unknown symbols must
+    // reach the tokenizer so it is reported instead of splitting the group.
+    let groups = split_groups(text, r"(?m)^\s*$", |group| {
+        let offset = group.as_ptr() as usize - text.as_ptr() as usize;
+        line_offset.1 += text[line_offset.0..offset].matches('\n').count();
+        line_offset.0 = offset;
+        let mut people: Vec<Vec<usize>> = Vec::new();
+        for (i, line) in group.lines().enumerate() {
+            let (tokens, characters) = tokenizer.tokens(line);
+            unrecognised.extend(characters.into_iter().map(|(column, character)| {
+                UnrecognisedCharacter {
+                    line: line_offset.1 + i + 1,
+                    column,
+                    character,
+                }
+            }));
+            if !tokens.is_empty() {
+                people.push(tokens.into_iter().map(|token| table.index(token)).collect());
+            }
+        }
+        people
+    });
+    if !unrecognised.is_empty() {
+        return Err(TokenizeError::Unrecognised(unrecognised));
+    }
+    if table.names.len() > AnswerSet::<QUESTION_WORDS>::CAPACITY {
+        return Err(TokenizeError::TooManyQuestions(table.names.len()));
+    }
+    let groups: Vec<u32> = groups
+        .into_iter()
+        .map(|people| {
+            let people = people
+                .into_iter()
+                .map(|person| person.into_iter().collect::<AnswerSet<QUESTION_WORDS>>());
+            apply_quorum(people, quorum).len() as u32
         })
-    }).filter(|set| set.len() > 0).collect();
-    answers.iter().fold(answers[0].clone(), |acc, set| {
-        acc.intersection(set).map(|elem| elem.to_string()).collect()
+        .collect();
+    let total = groups.iter().sum();
+    Ok(TokenizedCount {
+        questions: table.names,
+        count: QuorumCount { groups, total },
     })
 }
 
-fn get_flight_answers_all(text: &str) -> Vec<HashSet<String>> {
-    get_flight_answers_generic(text, |elem| get_group_all(elem))
+pub fn count_flight_answers_tokenized(
+    filename: &str,
+    tokenizer: &Tokenizer,
+    quorum: Quorum,
+) -> Result<TokenizedCount, TokenizeError> {
+    let text = fs::read_to_string(filename).unwrap();
+    count_tokenized(&text, tokenizer, quorum)
 }
 
-pub fn count_flight_answers_all(filename: &str) -> u32 {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionStats {
+    pub question: char,
+    pub groups: u32,
+    pub unanimous_groups: u32,
+    pub individuals: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlightStats {
+    pub groups: u32,
+    pub individuals: u32,
+    pub questions: Vec<QuestionStats>,
+    pub group_sizes: BTreeMap<usize, u32>,
+}
+
+impl FlightStats {
+    pub fn universal_questions(&self) -> Vec<char> {
+        self.questions
+            .iter()
+            .filter(|stats| stats.unanimous_groups == self.groups)
+            .map(|stats| stats.question)
+            .collect()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        text.push_str(&format!("Groups: {}\n", self.groups));
+        text.push_str(&format!("Individuals: {}\n", self.individuals));
+        text.push_str("Group sizes:\n");
+        for (size, count) in &self.group_sizes {
+            text.push_str(&format!("  {:>3} people: {}\n", size, count));
+        }
+        text.push_str("Questions:\n");
+        text.push_str("  question  groups  unanimous  individuals\n");
+        for stats in &self.questions {
+            text.push_str(&format!(
+                "  {:>8}  {:>6}  {:>9}  {:>11}\n",
+                stats.question, stats.groups, stats.unanimous_groups, stats.individuals
+            ));
+        }
+        let universal = self.universal_questions();
+        if universal.is_empty() {
+            text.push_str("Universal questions: none\n");
+        } else {
+            text.push_str(&format!(
+                "Universal questions: {}\n",
+                universal.into_iter().collect::<String>()
+            ));
+        }
+        text
+    }
+
+    pub fn questions_to_csv(&self) -> String {
+        let mut csv = String::from("question,groups,unanimous_groups,individuals,universal\n");
+        for stats in &self.questions {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                stats.question,
+                stats.groups,
+                stats.unanimous_groups,
+                stats.individuals,
+                stats.unanimous_groups == self.groups
+            ));
+        }
+        csv
+    }
+
+    pub fn group_sizes_to_csv(&self) -> String {
+        let mut csv = String::from("group_size,groups\n");
+        for (size, count) in &self.group_sizes {
+            csv.push_str(&format!("{},{}\n", size, count));
+        }
+        csv
+    }
+}
+
+fn collect_flight_stats(text: &str) -> FlightStats {
+    let mut stats = FlightStats {
+        groups: 0,
+        individuals: 0,
+        questions: Vec::new(),
+        group_sizes: BTreeMap::new(),
+    };
+    let mut groups = [0; 26];
+    let mut unanimous_groups = [0; 26];
+    let mut individuals = [0; 26];
+    for (group_size, tally) in get_flight_answers_generic(text, get_group_tally) {
+        if group_size == 0 {
+            continue;
+        }
+        stats.groups += 1;
+        stats.individuals += group_size as u32;
+        *stats.group_sizes.entry(group_size).or_insert(0) += 1;
+        for (answer, &count) in tally.iter().enumerate().take(26) {
+            if count > 0 {
+                groups[answer] += 1;
+                individuals[answer] += count as u32;
+            }
+            if count == group_size {
+                unanimous_groups[answer] += 1;
+            }
+        }
+    }
+    stats.questions = (0..26)
+        .filter(|&answer| groups[answer] > 0)
+        .map(|answer| QuestionStats {
+            question: (b'a' + answer as u8) as char,
+            groups: groups[answer],
+            unanimous_groups: unanimous_groups[answer],
+            individuals: individuals[answer],
+        })
+        .collect();
+    stats
+}
+
+pub fn get_flight_stats(filename: &str) -> FlightStats {
     let text = fs::read_to_string(filename).unwrap();
-    get_flight_answers_all(&text).iter().fold(0, |acc, set| acc + set.len() as u32)
+    collect_flight_stats(&text)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{get_group_answers, get_flight_answers, count_flight_answers, get_group_all, get_flight_answers_all, count_flight_answers_all};
-    use std::collections::HashSet;
+    use crate::{get_flight_answers_quorum, count_flight_answers, count_flight_answers_all, count_quorum, count_flight_answers_quorum, get_group_quorum, collect_flight_stats, get_flight_stats, count_tokenized, count_flight_answers_tokenized, AnswerSet, Quorum, TokenizeError, Tokenizer, UnrecognisedCharacter};
+    use std::collections::BTreeMap;
     use std::fs;
 
     #[test]
     fn test_group_answers() {
         let text = "abcx\n\rabcy\n\rabcz";
-        let mut set = HashSet::new();
-        set.insert("a".to_string());
-        set.insert("b".to_string());
-        set.insert("c".to_string());
-        set.insert("x".to_string());
-        set.insert("y".to_string());
-        set.insert("z".to_string());
-        assert_eq!(set, get_group_answers(text));
+        let mut set = AnswerSet::new();
+        set.insert(0);
+        set.insert(1);
+        set.insert(2);
+        set.insert(23);
+        set.insert(24);
+        set.insert(25);
+        assert_eq!(set, get_group_quorum(text, Quorum::ANYONE));
     }
 
     #[test]
     fn test_flight_answers() {
         let text = fs::read_to_string("data/example.txt").unwrap();
-        let flight = get_flight_answers(&text);
+        let flight = get_flight_answers_quorum(&text, Quorum::ANYONE);
         assert_eq!(3, flight[0].len());
         assert_eq!(3, flight[1].len());
         assert_eq!(3, flight[2].len());
@@ -87,37 +559,37 @@ mod tests {
     #[test]
     fn test_get_group_all_1() {
         let text = "abc";
-        assert_eq!(3, get_group_all(&text).len());
+        assert_eq!(3, get_group_quorum(text, Quorum::EVERYONE).len());
     }
 
     #[test]
     fn test_get_group_all_2() {
         let text = "abcx\n\rabcy\n\rabcz";
-        assert_eq!(3, get_group_all(&text).len());
+        assert_eq!(3, get_group_quorum(text, Quorum::EVERYONE).len());
     }
 
     #[test]
     fn test_get_group_all_3() {
         let text = "ab\n\rac";
-        assert_eq!(1, get_group_all(&text).len());
+        assert_eq!(1, get_group_quorum(text, Quorum::EVERYONE).len());
     }
 
     #[test]
     fn test_get_group_all_4() {
         let text = "a\n\rb\n\rc";
-        assert_eq!(0, get_group_all(&text).len());
+        assert_eq!(0, get_group_quorum(text, Quorum::EVERYONE).len());
     }
 
     #[test]
     fn test_get_group_all_5() {
         let text = "a\n\ra\n\ra";
-        assert_eq!(1, get_group_all(&text).len());
+        assert_eq!(1, get_group_quorum(text, Quorum::EVERYONE).len());
     }
 
     #[test]
     fn test_flight_answers_all() {
         let text = fs::read_to_string("data/example.txt").unwrap();
-        let flight = get_flight_answers_all(&text);
+        let flight = get_flight_answers_quorum(&text, Quorum::EVERYONE);
         assert_eq!(3, flight[0].len());
         assert_eq!(0, flight[1].len());
         assert_eq!(1, flight[2].len());
@@ -130,6 +602,247 @@ mod tests {
         assert_eq!(6, count_flight_answers_all("data/example.txt"));
     }
 
+    #[test]
+    fn test_group_quorum() {
+        let text = "abcx\n\rabcy\n\rabz\n\rax";
+        assert_eq!(6, get_group_quorum(text, Quorum::AtLeast(1)).len());
+        assert_eq!(4, get_group_quorum(text, Quorum::AtLeast(2)).len());
+        assert_eq!(2, get_group_quorum(text, Quorum::AtLeast(3)).len());
+        assert_eq!(1, get_group_quorum(text, Quorum::AtLeast(4)).len());
+        assert_eq!(2, get_group_quorum(text, Quorum::Exactly(1)).len());
+        assert_eq!(2, get_group_quorum(text, Quorum::Exactly(2)).len());
+        assert_eq!(4, get_group_quorum(text, Quorum::AtLeastPercent(50)).len());
+        assert_eq!(2, get_group_quorum(text, Quorum::AtLeastPercent(51)).len());
+        assert_eq!(1, get_group_quorum(text, Quorum::EVERYONE).len());
+    }
+
+    #[test]
+    fn test_group_quorum_counts_each_person_once() {
+        let text = "aab\nb";
+        assert_eq!(1, get_group_quorum(text, Quorum::Exactly(1)).len());
+        assert_eq!(1, get_group_quorum(text, Quorum::Exactly(2)).len());
+    }
+
+    #[test]
+    fn test_count_quorum() {
+        let text = fs::read_to_string("data/example.txt").unwrap();
+        let count = count_quorum(&text, Quorum::AtLeast(2));
+        assert_eq!(vec![0, 0, 1, 1, 0], count.groups);
+        assert_eq!(2, count.total);
+        let count = count_quorum(&text, Quorum::Exactly(1));
+        assert_eq!(vec![3, 3, 2, 0, 1], count.groups);
+        assert_eq!(9, count.total);
+    }
+
+    #[test]
+    fn test_quorum_special_cases() {
+        assert_eq!(11, count_flight_answers_quorum("data/example.txt", Quorum::ANYONE).total);
+        assert_eq!(6, count_flight_answers_quorum("data/example.txt", Quorum::EVERYONE).total);
+    }
+
+    #[test]
+    fn test_answer_set() {
+        let a: AnswerSet = vec![0, 3, 25].into_iter().collect();
+        let b: AnswerSet = vec![3, 4, 25].into_iter().collect();
+        assert_eq!(vec![0, 3, 4, 25], a.union(&b).iter().collect::<Vec<usize>>());
+        assert_eq!(vec![3, 25], a.intersection(&b).iter().collect::<Vec<usize>>());
+        assert_eq!(3, a.len());
+        assert!(AnswerSet::<1>::new().is_empty());
+    }
+
+    #[test]
+    fn test_wide_answer_set() {
+        let a: AnswerSet<2> = vec![1, 63, 64, 127].into_iter().collect();
+        let b: AnswerSet<2> = vec![63, 100, 127].into_iter().collect();
+        assert_eq!(128, AnswerSet::<2>::CAPACITY);
+        assert_eq!(5, a.union(&b).len());
+        assert_eq!(vec![63, 127], a.intersection(&b).iter().collect::<Vec<usize>>());
+        assert!(a.contains(64));
+        assert!(!a.contains(65));
+        assert!(!a.contains(128));
+    }
+
+    #[test]
+    fn test_million_groups() {
+        let example = fs::read_to_string("data/example.txt").unwrap();
+        let groups = example.trim().split("\n\n").collect::<Vec<&str>>();
+        let text = groups.repeat(200_000).join("\n\n");
+        let count = count_quorum(&text, Quorum::ANYONE);
+        assert_eq!(1_000_000, count.groups.len());
+        assert_eq!(11 * 200_000, count.total);
+        assert_eq!(6 * 200_000, count_quorum(&text, Quorum::EVERYONE).total);
+    }
+
+    #[test]
+    fn test_flight_stats() {
+        let stats = get_flight_stats("data/example.txt");
+        assert_eq!(5, stats.groups);
+        assert_eq!(11, stats.individuals);
+        let questions = stats
+            .questions
+            .iter()
+            .map(|q| (q.question, q.groups, q.unanimous_groups, q.individuals))
+            .collect::<Vec<(char, u32, u32, u32)>>();
+        assert_eq!(vec![('a', 4, 3, 8), ('b', 4, 2, 4), ('c', 3, 1, 3)], questions);
+        let mut group_sizes = BTreeMap::new();
+        group_sizes.insert(1, 2);
+        group_sizes.insert(2, 1);
+        group_sizes.insert(3, 1);
+        group_sizes.insert(4, 1);
+        assert_eq!(group_sizes, stats.group_sizes);
+        assert!(stats.universal_questions().is_empty());
+    }
+
+    #[test]
+    fn test_universal_questions() {
+        let stats = collect_flight_stats("ab\nba\n\nbc\n\nb\n");
+        assert_eq!(vec!['b'], stats.universal_questions());
+    }
+
+    #[test]
+    fn test_flight_stats_output() {
+        let stats = collect_flight_stats("ab\nb\n\nb");
+        assert_eq!(
+            "question,groups,unanimous_groups,individuals,universal\n\
+             a,1,0,1,false\n\
+             b,2,2,3,true\n",
+            stats.questions_to_csv()
+        );
+        assert_eq!("group_size,groups\n1,1\n2,1\n", stats.group_sizes_to_csv());
+        assert_eq!(
+            "Groups: 2\n\
+             Individuals: 3\n\
+             Group sizes:\n\
+             \x20\x20  1 people: 1\n\
+             \x20\x20  2 people: 1\n\
+             Questions:\n\
+             \x20\x20question  groups  unanimous  individuals\n\
+             \x20\x20       a       1          0            1\n\
+             \x20\x20       b       2          2            3\n\
+             Universal questions: b\n",
+            stats.to_text()
+        );
+    }
+
+    #[test]
+    fn test_tokenized_lowercase_matches_default() {
+        let tokenizer = Tokenizer::lowercase();
+        let anyone = count_flight_answers_tokenized("data/answers.txt", &tokenizer, Quorum::ANYONE);
+        assert_eq!(6532, anyone.unwrap().count.total);
+        let everyone =
+            count_flight_answers_tokenized("data/answers.txt", &tokenizer, Quorum::EVERYONE);
+        assert_eq!(3427, everyone.unwrap().count.total);
+    }
+
+    #[test]
+    fn test_tokenized_alphabet() {
+        let tokenizer = Tokenizer::Alphabet("ABC123".chars().collect());
+        let text = "AB1\nB12\n\nC3\n3";
+        let count = count_tokenized(text, &tokenizer, Quorum::ANYONE).unwrap();
+        assert_eq!(vec![4, 2], count.count.groups);
+        let count = count_tokenized(text, &tokenizer, Quorum::EVERYONE).unwrap();
+        assert_eq!(vec![2, 1], count.count.groups);
+        assert_eq!(vec!["A", "B", "C", "1", "2", "3"], count.questions);
+    }
+
+    #[test]
+    fn test_tokenized_delimited() {
+        let tokenizer = Tokenizer::delimited(&[',']);
+        let text = "q1,q12,q3\nq12, q3\n\nq12\nq2,q12,q2";
+        let count = count_tokenized(text, &tokenizer, Quorum::ANYONE).unwrap();
+        assert_eq!(vec!["q1", "q12", "q3", "q2"], count.questions);
+        assert_eq!(vec![3, 2], count.count.groups);
+        let count = count_tokenized(text, &tokenizer, Quorum::EVERYONE).unwrap();
+        assert_eq!(vec![2, 1], count.count.groups);
+        assert_eq!(3, count.count.total);
+    }
+
+    #[test]
+    fn test_tokenized_reports_unrecognised() {
+        let text = "abc\nab?\n\na\nXa";
+        let error = count_tokenized(text, &Tokenizer::lowercase(), Quorum::ANYONE).unwrap_err();
+        assert_eq!(
+            TokenizeError::Unrecognised(vec![
+                UnrecognisedCharacter {
+                    line: 2,
+                    column: 3,
+                    character: '?'
+                },
+                UnrecognisedCharacter {
+                    line: 5,
+                    column: 1,
+                    character: 'X'
+                },
+            ]),
+            error
+        );
+        assert_eq!(
+            "unrecognised characters: '?' at line 2, column 3, 'X' at line 5, column 1",
+            error.to_string()
+        );
+        let tokenizer = Tokenizer::delimited(&[',']);
+        let error = count_tokenized("q1;q2", &tokenizer, Quorum::ANYONE).unwrap_err();
+        assert_eq!(
+            TokenizeError::Unrecognised(vec![UnrecognisedCharacter {
+                line: 1,
+                column: 3,
+                character: ';'
+            }]),
+            error
+        );
+    }
+
+    #[test]
+    fn test_tokenized_symbol_lines() {
+        let error = count_tokenized("abc\n?\nab", &Tokenizer::lowercase(), Quorum::EVERYONE);
+        assert_eq!(
+            Err(TokenizeError::Unrecognised(vec![UnrecognisedCharacter {
+                line: 2,
+                column: 1,
+                character: '?'
+            }])),
+            error
+        );
+        let tokenizer = Tokenizer::Alphabet(vec!['+', '-']);
+        let count = count_tokenized("+-\n+", &tokenizer, Quorum::ANYONE).unwrap();
+        assert_eq!(vec![2], count.count.groups);
+        let count = count_tokenized("+-\n+", &tokenizer, Quorum::EVERYONE).unwrap();
+        assert_eq!(vec![1], count.count.groups);
+        let count = count_tokenized("+-\n \t\n+", &tokenizer, Quorum::ANYONE).unwrap();
+        assert_eq!(vec![2, 1], count.count.groups);
+    }
+
+    #[test]
+    fn test_tokenized_word_chars() {
+        let text = "a-b,c_d\nc_d";
+        let count = count_tokenized(text, &Tokenizer::delimited(&[',']), Quorum::ANYONE);
+        assert_eq!(
+            Err(TokenizeError::Unrecognised(vec![UnrecognisedCharacter {
+                line: 1,
+                column: 2,
+                character: '-'
+            }])),
+            count
+        );
+        let tokenizer = Tokenizer::Delimited {
+            delimiters: vec![','],
+            word_chars: vec!['-'],
+        };
+        let count = count_tokenized("a-b,c\nc,a-b", &tokenizer, Quorum::EVERYONE).unwrap();
+        assert_eq!(vec!["a-b", "c"], count.questions);
+        assert_eq!(vec![2], count.count.groups);
+    }
+
+    #[test]
+    fn test_tokenized_too_many_questions() {
+        let text = (0..300).map(|i| format!("q{}", i)).collect::<Vec<String>>().join(",");
+        let tokenizer = Tokenizer::delimited(&[',']);
+        assert_eq!(
+            Err(TokenizeError::TooManyQuestions(300)),
+            count_tokenized(&text, &tokenizer, Quorum::ANYONE)
+        );
+    }
+
     #[test]
     fn test_task1() {
         assert_eq!(6532, count_flight_answers("data/answers.txt"));
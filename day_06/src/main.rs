@@ -1,7 +1,15 @@
+use day_06::Quorum;
+
 fn main() {
     let count = day_06::count_flight_answers("data/answers.txt");
     println!("Number of answers for flight: {}", count);
 
     let count = day_06::count_flight_answers_all("data/answers.txt");
     println!("Number of answers where all answered yes: {}", count);
-}
\ No newline at end of file
+
+    let count = day_06::count_flight_answers_quorum("data/answers.txt", Quorum::AtLeastPercent(50));
+    println!("Number of answers where at least half answered yes: {}", count.total);
+
+    let stats = day_06::get_flight_stats("data/answers.txt");
+    print!("{}", stats.to_text());
+}
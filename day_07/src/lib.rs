@@ -1,10 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 
 struct Rule {
     bag_name: String,
     content_strings: Vec<(u32, String)>,
     contained_in: Vec<String>,
+    defined: bool,
 }
 
 fn get_rule(text: &str) -> Rule {
@@ -16,6 +21,7 @@ fn get_rule(text: &str) -> Rule {
             bag_name: cap[1].to_string(),
             content_strings: Vec::new(),
             contained_in: Vec::new(),
+            defined: true,
         }
     } else {
         let re_contents = regex::Regex::new(r" (\d+) ([a-z\s]+) bags?(,|\.)").unwrap();
@@ -28,33 +34,11 @@ fn get_rule(text: &str) -> Rule {
             bag_name: cap[1].to_string(),
             content_strings: contents,
             contained_in: Vec::new(),
+            defined: true,
         }
     }
 }
 
-pub fn count_bags_carrying_bag(filename: &str, bag: &str) -> u32 {
-    let text = fs::read_to_string(filename).unwrap();
-    let map = construct_map(&text);
-    let vec = go_up_in_map(&map, bag);
-    let set: HashSet<_> = vec.iter().collect();
-    set.len() as u32
-}
-
-fn go_up_in_map(map: &HashMap<String, Rule>, name: &str) -> Vec<String> {
-    let next_rule = map.get(name).unwrap();
-    if next_rule.contained_in.is_empty() {
-        Vec::new()
-    } else {
-        let mut ancestors = Vec::new();
-        for rule in next_rule.contained_in.iter() {
-            ancestors.push(rule.to_string());
-            let mut vec = go_up_in_map(map, rule);
-            ancestors.append(&mut vec);
-        }
-        ancestors
-    }
-}
-
 fn construct_map(text: &str) -> HashMap<String, Rule> {
     let mut map = HashMap::new();
     for line in text.lines() {
@@ -70,6 +54,7 @@ fn add_rule_to_map(map: &mut HashMap<String, Rule>, rule: Rule) {
         Some(found_rule) => {
             current_rule = found_rule;
             current_rule.content_strings = rule.content_strings;
+            current_rule.defined = true;
         },
         None => current_rule = rule,
     };
@@ -85,6 +70,7 @@ fn add_rule_to_map(map: &mut HashMap<String, Rule>, rule: Rule) {
                     bag_name: content_rule_string.to_string(),
                     content_strings: Vec::new(),
                     contained_in: vec![current_rule.bag_name.to_string()],
+                    defined: false,
                 };
             }
         }
@@ -93,24 +79,737 @@ fn add_rule_to_map(map: &mut HashMap<String, Rule>, rule: Rule) {
     map.insert(current_rule.bag_name.to_string(), current_rule);
 }
 
-fn count_required_bags(map: &HashMap<String, Rule>, name: &str) -> u32 {
-    let mut sum = 1;
-    let rule = map.get(name).unwrap();
-    for (num, bag) in rule.content_strings.iter() {
-        sum += num*count_required_bags(map, bag);
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BagError {
+    UnknownBag(String),
+    Cycle(Vec<String>),
+    Overflow(String),
+    MissingWeight(String),
+}
+
+impl fmt::Display for BagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BagError::UnknownBag(name) => write!(f, "unknown bag \"{}\"", name),
+            BagError::Cycle(path) => write!(f, "bags contain themselves: {}", path.join(" -> ")),
+            BagError::Overflow(name) => write!(f, "number of bags in \"{}\" overflows", name),
+            BagError::MissingWeight(name) => write!(f, "no weight given for \"{}\"", name),
+        }
     }
-    sum
 }
 
-pub fn count_individual_bags(filename: &str, bag: &str) -> u32 {
-    let text = fs::read_to_string(filename).unwrap();
-    let map = construct_map(&text);
-    count_required_bags(&map, bag) - 1
+impl std::error::Error for BagError {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    InProgress,
+    Done,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Subgraph {
+    All,
+    ReachableFrom(String),
+    LeadingTo(String),
+}
+
+type Edges<'a> = Vec<(&'a str, Vec<(u32, &'a str)>)>;
+
+pub struct BagGraph {
+    map: HashMap<String, Rule>,
+}
+
+impl BagGraph {
+    pub fn new(text: &str) -> BagGraph {
+        BagGraph {
+            map: construct_map(text),
+        }
+    }
+
+    pub fn from_file(filename: &str) -> BagGraph {
+        let text = fs::read_to_string(filename).unwrap();
+        BagGraph::new(&text)
+    }
+
+    fn rule(&self, name: &str) -> Result<&Rule, BagError> {
+        self.map
+            .get(name)
+            .ok_or_else(|| BagError::UnknownBag(name.to_string()))
+    }
+
+    fn sorted_names(&self) -> Vec<&str> {
+        let mut names = self.map.keys().map(|name| name.as_str()).collect::<Vec<&str>>();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        self.topological_order(&self.sorted_names()).err()
+    }
+
+    fn topological_order(&self, starts: &[&str]) -> Result<Vec<&str>, Vec<String>> {
+        let mut visits: HashMap<&str, Visit> = HashMap::new();
+        let mut order = Vec::new();
+        for &start in starts {
+            let start = match self.map.get_key_value(start) {
+                Some((start, _)) if !visits.contains_key(start.as_str()) => start.as_str(),
+                _ => continue,
+            };
+            visits.insert(start, Visit::InProgress);
+            let mut stack = vec![(start, 0)];
+            while let Some((name, next_child)) = stack.last_mut() {
+                let rule = &self.map[*name];
+                if *next_child == rule.content_strings.len() {
+                    visits.insert(name, Visit::Done);
+                    order.push(*name);
+                    stack.pop();
+                    continue;
+                }
+                let child = rule.content_strings[*next_child].1.as_str();
+                *next_child += 1;
+                match visits.get(child) {
+                    Some(Visit::InProgress) => {
+                        let position = stack.iter().position(|(name, _)| *name == child).unwrap();
+                        let mut path = stack[position..]
+                            .iter()
+                            .map(|(name, _)| name.to_string())
+                            .collect::<Vec<String>>();
+                        path.push(child.to_string());
+                        return Err(path);
+                    }
+                    Some(Visit::Done) => (),
+                    None => {
+                        visits.insert(child, Visit::InProgress);
+                        stack.push((child, 0));
+                    }
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    fn order_from(&self, name: &str) -> Result<Vec<&str>, BagError> {
+        self.rule(name)?;
+        self.topological_order(&[name]).map_err(BagError::Cycle)
+    }
+
+    pub fn ancestors(&self, name: &str) -> Result<HashSet<String>, BagError> {
+        self.traverse(name, |rule| rule.contained_in.iter().collect())
+    }
+
+    pub fn descendants(&self, name: &str) -> Result<HashSet<String>, BagError> {
+        self.traverse(name, |rule| rule.content_strings.iter().map(|(_, bag)| bag).collect())
+    }
+
+    fn traverse<F>(&self, name: &str, next: F) -> Result<HashSet<String>, BagError>
+    where
+        F: Fn(&Rule) -> Vec<&String>,
+    {
+        let mut found = HashSet::new();
+        let mut stack = next(self.rule(name)?);
+        while let Some(bag) = stack.pop() {
+            if found.insert(bag.to_string()) {
+                stack.append(&mut next(self.rule(bag)?));
+            }
+        }
+        Ok(found)
+    }
+
+    fn weighted_totals<'a>(
+        &self,
+        order: Vec<&'a str>,
+        weights: Option<&HashMap<String, u128>>,
+    ) -> Result<HashMap<&'a str, u128>, BagError> {
+        let mut totals: HashMap<&str, u128> = HashMap::new();
+        for bag in order {
+            let mut total = match weights {
+                Some(weights) => *weights
+                    .get(bag)
+                    .ok_or_else(|| BagError::MissingWeight(bag.to_string()))?,
+                None => 1,
+            };
+            for (num, content) in self.map[bag].content_strings.iter() {
+                total = u128::from(*num)
+                    .checked_mul(totals[content.as_str()])
+                    .and_then(|bags| bags.checked_add(total))
+                    .ok_or_else(|| BagError::Overflow(bag.to_string()))?;
+            }
+            totals.insert(bag, total);
+        }
+        Ok(totals)
+    }
+
+    pub fn required_bags(&self, name: &str) -> Result<u128, BagError> {
+        let totals = self.weighted_totals(self.order_from(name)?, None)?;
+        Ok(totals[name])
+    }
+
+    pub fn count_required_bags(&self, name: &str) -> Result<u64, BagError> {
+        let total = self.required_bags(name)?;
+        u64::try_from(total).map_err(|_| BagError::Overflow(name.to_string()))
+    }
+
+    pub fn leaf_breakdown(&self, name: &str) -> Result<BTreeMap<String, u128>, BagError> {
+        let mut breakdowns: HashMap<&str, BTreeMap<String, u128>> = HashMap::new();
+        for bag in self.order_from(name)? {
+            let mut breakdown = BTreeMap::new();
+            for (num, content) in self.map[bag].content_strings.iter() {
+                let num = u128::from(*num);
+                let content_breakdown = &breakdowns[content.as_str()];
+                if self.map[content].content_strings.is_empty() {
+                    add_checked(&mut breakdown, content, num, bag)?;
+                }
+                for (leaf, count) in content_breakdown {
+                    let count = num
+                        .checked_mul(*count)
+                        .ok_or_else(|| BagError::Overflow(bag.to_string()))?;
+                    add_checked(&mut breakdown, leaf, count, bag)?;
+                }
+            }
+            breakdowns.insert(bag, breakdown);
+        }
+        Ok(breakdowns.remove(name).unwrap())
+    }
+
+    fn subgraph_edges(&self, subgraph: &Subgraph) -> Result<Edges<'_>, BagError> {
+        let names: HashSet<String> = match subgraph {
+            Subgraph::All => self.map.keys().cloned().collect(),
+            Subgraph::ReachableFrom(name) => {
+                let mut names = self.descendants(name)?;
+                names.insert(name.to_string());
+                names
+            }
+            Subgraph::LeadingTo(name) => {
+                let mut names = self.ancestors(name)?;
+                names.insert(name.to_string());
+                names
+            }
+        };
+        Ok(self
+            .sorted_names()
+            .into_iter()
+            .filter(|name| names.contains(*name))
+            .map(|name| {
+                let contents = self.map[name]
+                    .content_strings
+                    .iter()
+                    .filter(|(_, bag)| names.contains(bag))
+                    .map(|(num, bag)| (*num, bag.as_str()))
+                    .collect();
+                (name, contents)
+            })
+            .collect())
+    }
+
+    pub fn paths(&self, from: &str, to: &str) -> Result<Vec<ContainmentPath>, BagError> {
+        self.rule(from)?;
+        self.rule(to)?;
+        let mut paths = Vec::new();
+        let mut stack: Vec<(&str, usize, u128)> = vec![(from, 0, 1)];
+        while let Some(&mut (name, ref mut next_child, count)) = stack.last_mut() {
+            let contents = &self.map[name].content_strings;
+            if *next_child == contents.len() {
+                stack.pop();
+                continue;
+            }
+            let (num, child) = &contents[*next_child];
+            *next_child += 1;
+            if stack.iter().any(|(bag, _, _)| bag == child) {
+                continue;
+            }
+            let count = count
+                .checked_mul(u128::from(*num))
+                .ok_or_else(|| BagError::Overflow(name.to_string()))?;
+            if child == to {
+                let mut bags = stack
+                    .iter()
+                    .map(|(bag, _, _)| bag.to_string())
+                    .collect::<Vec<String>>();
+                bags.push(child.to_string());
+                paths.push(ContainmentPath { bags, count });
+            } else {
+                stack.push((child, 0, count));
+            }
+        }
+        Ok(paths)
+    }
+
+    pub fn containers_with_counts(&self, name: &str) -> Result<BTreeMap<String, u128>, BagError> {
+        let ancestors = self.ancestors(name)?;
+        let mut starts = ancestors.iter().map(|bag| bag.as_str()).collect::<Vec<&str>>();
+        starts.sort_unstable();
+        let order = self.topological_order(&starts).map_err(BagError::Cycle)?;
+        let mut counts: HashMap<&str, u128> = HashMap::new();
+        for bag in order {
+            let mut count: u128 = 0;
+            for (num, content) in self.map[bag].content_strings.iter() {
+                let content_count = if content == name { 1 } else { counts[content.as_str()] };
+                count = u128::from(*num)
+                    .checked_mul(content_count)
+                    .and_then(|bags| bags.checked_add(count))
+                    .ok_or_else(|| BagError::Overflow(bag.to_string()))?;
+            }
+            counts.insert(bag, count);
+        }
+        Ok(ancestors
+            .into_iter()
+            .map(|bag| {
+                let count = counts[bag.as_str()];
+                (bag, count)
+            })
+            .collect())
+    }
+
+    pub fn common_containers(&self, first: &str, second: &str) -> Result<Vec<String>, BagError> {
+        let first = self.ancestors(first)?;
+        let second = self.ancestors(second)?;
+        let mut common = first.intersection(&second).cloned().collect::<Vec<String>>();
+        common.sort_unstable();
+        Ok(common)
+    }
+
+    pub fn nesting_depth(&self, name: &str) -> Result<usize, BagError> {
+        let mut depths: HashMap<&str, usize> = HashMap::new();
+        for bag in self.order_from(name)? {
+            let depth = self.map[bag]
+                .content_strings
+                .iter()
+                .map(|(_, content)| depths[content.as_str()] + 1)
+                .max()
+                .unwrap_or(0);
+            depths.insert(bag, depth);
+        }
+        Ok(depths[name])
+    }
+
+    pub fn max_nesting_depth(&self) -> Result<usize, BagError> {
+        self.sorted_names()
+            .into_iter()
+            .map(|name| self.nesting_depth(name))
+            .try_fold(0, |max, depth| depth.map(|depth| max.max(depth)))
+    }
+
+    pub fn answer(&self, query: &Query) -> Result<String, BagError> {
+        let lines = match query {
+            Query::Paths { from, to } => self
+                .paths(from, to)?
+                .into_iter()
+                .map(|path| format!("{} ({} bags)", path.bags.join(" -> "), path.count))
+                .collect(),
+            Query::Containers(name) => self
+                .containers_with_counts(name)?
+                .into_iter()
+                .map(|(bag, count)| format!("{}: {}", bag, count))
+                .collect(),
+            Query::Common(first, second) => self.common_containers(first, second)?,
+            Query::Depth(name) => vec![self.nesting_depth(name)?.to_string()],
+        };
+        Ok(lines.join("\n"))
+    }
+
+    pub fn bags_within_budget(
+        &self,
+        budget: u128,
+        weights: Option<&HashMap<String, u128>>,
+    ) -> Result<Vec<Packing>, BagError> {
+        let order = self
+            .topological_order(&self.sorted_names())
+            .map_err(BagError::Cycle)?;
+        let mut packings = self
+            .weighted_totals(order, weights)?
+            .into_iter()
+            .filter(|(_, cost)| *cost <= budget)
+            .map(|(bag, cost)| Packing {
+                bag: bag.to_string(),
+                cost,
+            })
+            .collect::<Vec<Packing>>();
+        packings.sort_by(|a, b| a.cost.cmp(&b.cost).then_with(|| a.bag.cmp(&b.bag)));
+        Ok(packings)
+    }
+
+    pub fn best_carrier(
+        &self,
+        target: &str,
+        budget: u128,
+        weights: Option<&HashMap<String, u128>>,
+    ) -> Result<Option<Carrier>, BagError> {
+        let carried = self.containers_with_counts(target)?;
+        let best = self
+            .bags_within_budget(budget, weights)?
+            .into_iter()
+            .filter_map(|packing| {
+                carried.get(&packing.bag).map(|&carried| Carrier {
+                    bag: packing.bag,
+                    cost: packing.cost,
+                    carried,
+                })
+            })
+            .max_by(compare_carriers);
+        Ok(best)
+    }
+
+    pub fn to_rules(&self) -> String {
+        let mut text = String::new();
+        for name in self.sorted_names() {
+            if !self.map[name].defined {
+                continue;
+            }
+            let contents = &self.map[name].content_strings;
+            let contents = if contents.is_empty() {
+                "no other bags".to_string()
+            } else {
+                contents
+                    .iter()
+                    .map(|(num, bag)| {
+                        let plural = if *num == 1 { "bag" } else { "bags" };
+                        format!("{} {} {}", num, bag, plural)
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            };
+            text.push_str(&format!("{} bags contain {}.\n", name, contents));
+        }
+        text
+    }
+
+    pub fn to_dot(&self, subgraph: &Subgraph) -> Result<String, BagError> {
+        let edges = self.subgraph_edges(subgraph)?;
+        let mut dot = String::from("digraph bags {\n");
+        for (name, _) in edges.iter() {
+            dot.push_str(&format!("    {};\n", quote(name)));
+        }
+        for (name, contents) in edges.iter() {
+            for (num, bag) in contents {
+                dot.push_str(&format!(
+                    "    {} -> {} [label=\"{}\"];\n",
+                    quote(name),
+                    quote(bag),
+                    num
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        Ok(dot)
+    }
+
+    pub fn to_json(&self, subgraph: &Subgraph) -> Result<String, BagError> {
+        let nodes = self
+            .subgraph_edges(subgraph)?
+            .into_iter()
+            .map(|(name, contents)| {
+                let contents = contents
+                    .into_iter()
+                    .map(|(num, bag)| format!("{{\"bag\": {}, \"count\": {}}}", quote(bag), num))
+                    .collect::<Vec<String>>();
+                format!("  {}: [{}]", quote(name), contents.join(", "))
+            })
+            .collect::<Vec<String>>();
+        Ok(format!("{{\n{}\n}}\n", nodes.join(",\n")))
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            _ if character < '\u{20}' => {
+                quoted.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            _ => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Full 256-bit product as (high, low) halves.
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u128::from(u64::MAX);
+    let (a_high, a_low) = (a >> 64, a & mask);
+    let (b_high, b_low) = (b >> 64, b & mask);
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let middle = (low_low >> 64) + (high_low & mask) + (low_high & mask);
+    let low = (middle << 64) | (low_low & mask);
+    let high = a_high * b_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+// Compares carried / cost exactly by cross-multiplying, so a carrier that
+// costs nothing ranks above every carrier that does.
+fn compare_carriers(a: &Carrier, b: &Carrier) -> Ordering {
+    wide_mul(a.carried, b.cost)
+        .cmp(&wide_mul(b.carried, a.cost))
+        .then_with(|| a.carried.cmp(&b.carried))
+        .then_with(|| b.bag.cmp(&a.bag))
+}
+
+fn add_checked(
+    breakdown: &mut BTreeMap<String, u128>,
+    leaf: &str,
+    count: u128,
+    bag: &str,
+) -> Result<(), BagError> {
+    let entry = breakdown.entry(leaf.to_string()).or_insert(0);
+    *entry = entry
+        .checked_add(count)
+        .ok_or_else(|| BagError::Overflow(bag.to_string()))?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packing {
+    pub bag: String,
+    pub cost: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Carrier {
+    pub bag: String,
+    pub cost: u128,
+    pub carried: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainmentPath {
+    pub bags: Vec<String>,
+    pub count: u128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Paths { from: String, to: String },
+    Containers(String),
+    Common(String, String),
+    Depth(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQueryError(String);
+
+impl fmt::Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid query \"{}\", expected one of: paths <bag> to <bag>, \
+             containers <bag>, common <bag> and <bag>, depth <bag>",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseQueryError {}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(text: &str) -> Result<Query, ParseQueryError> {
+        let text = text.trim();
+        let re_paths = regex::Regex::new(r"^paths ([a-z\s]+?) to ([a-z\s]+)$").unwrap();
+        let re_containers = regex::Regex::new(r"^containers ([a-z\s]+)$").unwrap();
+        let re_common = regex::Regex::new(r"^common ([a-z\s]+?) and ([a-z\s]+)$").unwrap();
+        let re_depth = regex::Regex::new(r"^depth ([a-z\s]+)$").unwrap();
+        if let Some(cap) = re_paths.captures(text) {
+            Ok(Query::Paths {
+                from: cap[1].to_string(),
+                to: cap[2].to_string(),
+            })
+        } else if let Some(cap) = re_containers.captures(text) {
+            Ok(Query::Containers(cap[1].to_string()))
+        } else if let Some(cap) = re_common.captures(text) {
+            Ok(Query::Common(cap[1].to_string(), cap[2].to_string()))
+        } else if let Some(cap) = re_depth.captures(text) {
+            Ok(Query::Depth(cap[1].to_string()))
+        } else {
+            Err(ParseQueryError(text.to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintWarning {
+    Unparsable { line: usize },
+    Undefined { bag: String, line: usize },
+    Duplicate { bag: String, line: usize, first_line: usize },
+    Conflicting { bag: String, line: usize, first_line: usize },
+    Unreachable { bag: String, line: usize },
+    Pluralisation { line: usize, text: String },
+    InvalidCount { line: usize, count: String },
+    UnknownRoot { bag: String },
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LintWarning::Unparsable { line } => write!(f, "line {}: rule cannot be parsed", line),
+            LintWarning::Undefined { bag, line } => {
+                write!(f, "line {}: \"{}\" is referenced but never defined", line, bag)
+            }
+            LintWarning::Duplicate {
+                bag,
+                line,
+                first_line,
+            } => write!(
+                f,
+                "line {}: \"{}\" is already defined on line {}",
+                line, bag, first_line
+            ),
+            LintWarning::Conflicting {
+                bag,
+                line,
+                first_line,
+            } => write!(
+                f,
+                "line {}: \"{}\" redefined with different contents than on line {}",
+                line, bag, first_line
+            ),
+            LintWarning::Unreachable { bag, line } => {
+                write!(f, "line {}: \"{}\" is unreachable", line, bag)
+            }
+            LintWarning::Pluralisation { line, text } => {
+                write!(f, "line {}: inconsistent pluralisation in \"{}\"", line, text)
+            }
+            LintWarning::InvalidCount { line, count } => {
+                write!(f, "line {}: count {} is too large", line, count)
+            }
+            LintWarning::UnknownRoot { bag } => write!(f, "root \"{}\" is not a known bag", bag),
+        }
+    }
+}
+
+pub fn lint_rules(text: &str, root: Option<&str>) -> Vec<LintWarning> {
+    let re_rule = regex::Regex::new(
+        r"^[a-z\s]+ bags contain (no other bags|\d+ [a-z\s]+ bags?(, \d+ [a-z\s]+ bags?)*)\.$",
+    )
+    .unwrap();
+    let re_contents = regex::Regex::new(r" (\d+) ([a-z\s]+?) (bags?)(,|\.)").unwrap();
+    let mut warnings = Vec::new();
+    let mut definitions: HashMap<String, (usize, Vec<(u32, String)>)> = HashMap::new();
+    let mut references: Vec<(String, usize)> = Vec::new();
+    let mut valid_lines = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_nr = i + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !re_rule.is_match(line) {
+            warnings.push(LintWarning::Unparsable { line: line_nr });
+            continue;
+        }
+        let mut counts_valid = true;
+        for cap in re_contents.captures_iter(line) {
+            if (&cap[1] == "1") != (&cap[3] == "bag") {
+                warnings.push(LintWarning::Pluralisation {
+                    line: line_nr,
+                    text: format!("{} {} {}", &cap[1], &cap[2], &cap[3]),
+                });
+            }
+            if cap[1].parse::<u32>().is_err() {
+                warnings.push(LintWarning::InvalidCount {
+                    line: line_nr,
+                    count: cap[1].to_string(),
+                });
+                counts_valid = false;
+            }
+        }
+        if !counts_valid {
+            continue;
+        }
+        let rule = get_rule(line);
+        for (_, bag) in rule.content_strings.iter() {
+            references.push((bag.to_string(), line_nr));
+        }
+        let mut contents = rule.content_strings.clone();
+        contents.sort();
+        match definitions.get(&rule.bag_name) {
+            Some((first_line, first_contents)) => {
+                let bag = rule.bag_name.to_string();
+                let (line, first_line) = (line_nr, *first_line);
+                if *first_contents == contents {
+                    warnings.push(LintWarning::Duplicate { bag, line, first_line });
+                } else {
+                    warnings.push(LintWarning::Conflicting { bag, line, first_line });
+                }
+            }
+            None => {
+                definitions.insert(rule.bag_name.to_string(), (line_nr, contents));
+            }
+        }
+        valid_lines.push(line);
+    }
+    let mut undefined = HashSet::new();
+    for (bag, line) in references.iter() {
+        if !definitions.contains_key(bag) && undefined.insert(bag) {
+            warnings.push(LintWarning::Undefined {
+                bag: bag.to_string(),
+                line: *line,
+            });
+        }
+    }
+    let graph = BagGraph::new(&valid_lines.join("\n"));
+    let connected = match root {
+        Some(root) => match (graph.ancestors(root), graph.descendants(root)) {
+            (Ok(mut connected), Ok(descendants)) => {
+                connected.extend(descendants);
+                connected.insert(root.to_string());
+                connected
+            }
+            _ => {
+                warnings.push(LintWarning::UnknownRoot {
+                    bag: root.to_string(),
+                });
+                return warnings;
+            }
+        },
+        None => graph
+            .map
+            .values()
+            .filter(|rule| !rule.content_strings.is_empty() || !rule.contained_in.is_empty())
+            .map(|rule| rule.bag_name.to_string())
+            .collect(),
+    };
+    let mut unreachable = definitions
+        .iter()
+        .filter(|(bag, _)| !connected.contains(*bag))
+        .map(|(bag, (line, _))| LintWarning::Unreachable {
+            bag: bag.to_string(),
+            line: *line,
+        })
+        .collect::<Vec<LintWarning>>();
+    unreachable.sort_by_key(|warning| match warning {
+        LintWarning::Unreachable { line, .. } => *line,
+        _ => 0,
+    });
+    warnings.append(&mut unreachable);
+    warnings
+}
+
+pub fn normalise_rules(filename: &str) -> String {
+    BagGraph::from_file(filename).to_rules()
+}
+
+pub fn count_bags_carrying_bag(filename: &str, bag: &str) -> Result<u32, BagError> {
+    let graph = BagGraph::from_file(filename);
+    Ok(graph.ancestors(bag)?.len() as u32)
+}
+
+pub fn count_individual_bags(filename: &str, bag: &str) -> Result<u64, BagError> {
+    let graph = BagGraph::from_file(filename);
+    Ok(graph.count_required_bags(bag)? - 1)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{get_rule, count_bags_carrying_bag, count_individual_bags};
+    use crate::{
+        get_rule, count_bags_carrying_bag, count_individual_bags, BagError, BagGraph,
+        ContainmentPath, Query, Subgraph, lint_rules, normalise_rules, LintWarning, Packing,
+        Carrier, quote, wide_mul,
+    };
+    use std::collections::HashMap;
 
     #[test]
     fn test_get_full_bag() {
@@ -134,22 +833,531 @@ mod tests {
     #[test]
     fn test_count_bags_carrying_bag() {
         let name = "shiny gold";
-        assert_eq!(4, count_bags_carrying_bag("data/example.txt", name));
+        assert_eq!(Ok(4), count_bags_carrying_bag("data/example.txt", name));
     }
 
     #[test]
     fn test_count_individual_bags() {
-        assert_eq!(32, count_individual_bags("data/example.txt", "shiny gold"));
+        assert_eq!(Ok(32), count_individual_bags("data/example.txt", "shiny gold"));
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let graph = BagGraph::from_file("data/example.txt");
+        assert_eq!(None, graph.find_cycle());
+        let text = "light red bags contain 1 bright white bag.\n\
+                    bright white bags contain 2 muted yellow bags, 1 faded blue bag.\n\
+                    muted yellow bags contain 1 light red bag.\n\
+                    faded blue bags contain no other bags.";
+        let graph = BagGraph::new(text);
+        assert_eq!(
+            Some(vec![
+                "bright white".to_string(),
+                "muted yellow".to_string(),
+                "light red".to_string(),
+                "bright white".to_string()
+            ]),
+            graph.find_cycle()
+        );
+    }
+
+    #[test]
+    fn test_cycle_safe_traversal() {
+        let text = "light red bags contain 1 bright white bag.\n\
+                    bright white bags contain 2 light red bags, 1 faded blue bag.\n\
+                    shiny gold bags contain 1 light red bag.\n\
+                    faded blue bags contain no other bags.";
+        let graph = BagGraph::new(text);
+        assert_eq!(3, graph.ancestors("light red").unwrap().len());
+        assert_eq!(3, graph.descendants("shiny gold").unwrap().len());
+        assert_eq!(
+            Err(BagError::Cycle(vec![
+                "light red".to_string(),
+                "bright white".to_string(),
+                "light red".to_string()
+            ])),
+            graph.count_required_bags("shiny gold")
+        );
+        assert_eq!(Ok(1), graph.count_required_bags("faded blue"));
+    }
+
+    #[test]
+    fn test_unknown_bag() {
+        let unknown = BagError::UnknownBag("plaid magenta".to_string());
+        let carrying = count_bags_carrying_bag("data/example.txt", "plaid magenta");
+        assert_eq!(Err(unknown.clone()), carrying);
+        assert_eq!(Err(unknown), count_individual_bags("data/example.txt", "plaid magenta"));
+    }
+
+    #[test]
+    fn test_leaf_breakdown() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let breakdown = graph.leaf_breakdown("shiny gold").unwrap();
+        assert_eq!(Some(&13), breakdown.get("faded blue"));
+        assert_eq!(Some(&16), breakdown.get("dotted black"));
+        assert_eq!(2, breakdown.len());
+        assert!(graph.leaf_breakdown("faded blue").unwrap().is_empty());
+        let breakdown = graph.leaf_breakdown("light red").unwrap();
+        assert_eq!(Some(&(13 + 2 * (2 * 13 + 9))), breakdown.get("faded blue"));
+        assert_eq!(Some(&(16 + 2 * (2 * 16))), breakdown.get("dotted black"));
+    }
+
+    fn level(i: usize) -> String {
+        i.to_string()
+            .bytes()
+            .map(|digit| (digit - b'0' + b'a') as char)
+            .collect()
+    }
+
+    fn diamond_chain(levels: usize) -> String {
+        let mut text = String::new();
+        for i in 0..levels {
+            for side in &["left", "right"] {
+                text.push_str(&format!(
+                    "{} {} bags contain 1 left {} bag, 1 right {} bag.\n",
+                    side,
+                    level(i),
+                    level(i + 1),
+                    level(i + 1)
+                ));
+            }
+        }
+        text.push_str(&format!("left {} bags contain no other bags.\n", level(levels)));
+        text.push_str(&format!("right {} bags contain no other bags.\n", level(levels)));
+        text
+    }
+
+    #[test]
+    fn test_required_bags_memoized() {
+        let graph = BagGraph::new(&diamond_chain(100));
+        assert_eq!(Ok((1 << 101) - 1), graph.required_bags("left a"));
+        assert_eq!(
+            Err(BagError::Overflow("left a".to_string())),
+            graph.count_required_bags("left a")
+        );
+        assert_eq!(Ok((1 << 41) - 1), graph.count_required_bags("left ga"));
+        let breakdown = graph.leaf_breakdown("left a").unwrap();
+        assert_eq!(Some(&(1 << 99)), breakdown.get("left baa"));
+    }
+
+    #[test]
+    fn test_required_bags_overflow() {
+        let graph = BagGraph::new(&diamond_chain(130));
+        assert!(matches!(graph.required_bags("left a"), Err(BagError::Overflow(_))));
+        assert!(matches!(graph.leaf_breakdown("left a"), Err(BagError::Overflow(_))));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let dot = graph.to_dot(&Subgraph::ReachableFrom("dark olive".to_string()));
+        assert_eq!(
+            Ok("digraph bags {\n\
+                \x20   \"dark olive\";\n\
+                \x20   \"dotted black\";\n\
+                \x20   \"faded blue\";\n\
+                \x20   \"dark olive\" -> \"faded blue\" [label=\"3\"];\n\
+                \x20   \"dark olive\" -> \"dotted black\" [label=\"4\"];\n\
+                }\n"
+                .to_string()),
+            dot
+        );
+        let dot = graph.to_dot(&Subgraph::All).unwrap();
+        assert_eq!(9, dot.lines().filter(|line| line.ends_with("\";")).count());
+        assert_eq!(13, dot.matches(" -> ").count());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let json = graph.to_json(&Subgraph::LeadingTo("bright white".to_string()));
+        assert_eq!(
+            Ok("{\n\
+                \x20 \"bright white\": [],\n\
+                \x20 \"dark orange\": [{\"bag\": \"bright white\", \"count\": 3}],\n\
+                \x20 \"light red\": [{\"bag\": \"bright white\", \"count\": 1}]\n\
+                }\n"
+                .to_string()),
+            json
+        );
+        assert_eq!(
+            Err(BagError::UnknownBag("plaid magenta".to_string())),
+            graph.to_json(&Subgraph::ReachableFrom("plaid magenta".to_string()))
+        );
+        let graph = BagGraph::new("dim\tred bags contain 2 light\u{b}blue bags.");
+        assert!(graph
+            .to_json(&Subgraph::All)
+            .unwrap()
+            .contains("\"dim\\u0009red\": [{\"bag\": \"light\\u000bblue\", \"count\": 2}]"));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!("\"a \\\"b\\\" c\\\\d\"", quote("a \"b\" c\\d"));
+        assert_eq!("\"a\\u000ab\\u000dc\\u001f\u{7f}\"", quote("a\nb\rc\u{1f}\u{7f}"));
+    }
+
+    #[test]
+    fn test_paths() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let path = |bags: &[&str], count| ContainmentPath {
+            bags: bags.iter().map(|bag| bag.to_string()).collect(),
+            count,
+        };
+        assert_eq!(
+            Ok(vec![
+                path(&["light red", "bright white", "shiny gold"], 1),
+                path(&["light red", "muted yellow", "shiny gold"], 4),
+            ]),
+            graph.paths("light red", "shiny gold")
+        );
+        assert_eq!(Ok(Vec::new()), graph.paths("shiny gold", "light red"));
+    }
+
+    #[test]
+    fn test_paths_in_cycle() {
+        let text = "light red bags contain 1 bright white bag, 2 shiny gold bags.\n\
+                    bright white bags contain 3 light red bags, 1 shiny gold bag.\n\
+                    shiny gold bags contain no other bags.";
+        let graph = BagGraph::new(text);
+        let counts = graph
+            .paths("light red", "shiny gold")
+            .unwrap()
+            .into_iter()
+            .map(|path| path.count)
+            .collect::<Vec<u128>>();
+        assert_eq!(vec![1, 2], counts);
+    }
+
+    #[test]
+    fn test_containers_with_counts() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let counts = graph.containers_with_counts("shiny gold").unwrap();
+        assert_eq!(
+            vec![
+                ("bright white".to_string(), 1),
+                ("dark orange".to_string(), 11),
+                ("light red".to_string(), 5),
+                ("muted yellow".to_string(), 2),
+            ],
+            counts.into_iter().collect::<Vec<(String, u128)>>()
+        );
+    }
+
+    #[test]
+    fn test_common_containers() {
+        let graph = BagGraph::from_file("data/example.txt");
+        assert_eq!(
+            Ok(vec!["dark orange".to_string(), "light red".to_string()]),
+            graph.common_containers("bright white", "muted yellow")
+        );
+        assert_eq!(Ok(Vec::new()), graph.common_containers("light red", "faded blue"));
+    }
+
+    #[test]
+    fn test_nesting_depth() {
+        let graph = BagGraph::from_file("data/example.txt");
+        assert_eq!(Ok(4), graph.nesting_depth("light red"));
+        assert_eq!(Ok(2), graph.nesting_depth("shiny gold"));
+        assert_eq!(Ok(0), graph.nesting_depth("faded blue"));
+        assert_eq!(Ok(4), graph.max_nesting_depth());
+    }
+
+    #[test]
+    fn test_query() {
+        assert_eq!(
+            Ok(Query::Paths {
+                from: "light red".to_string(),
+                to: "shiny gold".to_string()
+            }),
+            "paths light red to shiny gold".parse()
+        );
+        assert_eq!(
+            Ok(Query::Common("bright white".to_string(), "muted yellow".to_string())),
+            "common bright white and muted yellow".parse()
+        );
+        assert!("count shiny gold".parse::<Query>().is_err());
+        let graph = BagGraph::from_file("data/example.txt");
+        let query = "paths light red to shiny gold".parse().unwrap();
+        assert_eq!(
+            Ok("light red -> bright white -> shiny gold (1 bags)\n\
+                light red -> muted yellow -> shiny gold (4 bags)"
+                .to_string()),
+            graph.answer(&query)
+        );
+        assert_eq!(Ok("2".to_string()), graph.answer(&"depth shiny gold".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_lint_clean() {
+        let text = std::fs::read_to_string("data/bag_rules.txt").unwrap();
+        assert_eq!(Vec::<LintWarning>::new(), lint_rules(&text, None));
+        let text = std::fs::read_to_string("data/example.txt").unwrap();
+        assert_eq!(Vec::<LintWarning>::new(), lint_rules(&text, Some("shiny gold")));
+    }
+
+    #[test]
+    fn test_lint_warnings() {
+        let text = "light red bags contain 1 bright white bags, 2 muted yellow bag.\n\
+                    bright white bags contain 1 shiny gold bag.\n\
+                    bright white bags contain 1 shiny gold bag.\n\
+                    shiny gold bags contain no other bags.\n\
+                    shiny gold bags contain 2 faded blue bags.\n\
+                    faded blue bags contain no other bags.\n\
+                    dotted black bags contain no other bags.\n\
+                    \n\
+                    wavy red bags hold 2 faded blue bags.";
+        let warnings = lint_rules(text, None);
+        assert_eq!(
+            vec![
+                LintWarning::Pluralisation {
+                    line: 1,
+                    text: "1 bright white bags".to_string()
+                },
+                LintWarning::Pluralisation {
+                    line: 1,
+                    text: "2 muted yellow bag".to_string()
+                },
+                LintWarning::Duplicate {
+                    bag: "bright white".to_string(),
+                    line: 3,
+                    first_line: 2
+                },
+                LintWarning::Conflicting {
+                    bag: "shiny gold".to_string(),
+                    line: 5,
+                    first_line: 4
+                },
+                LintWarning::Unparsable { line: 9 },
+                LintWarning::Undefined {
+                    bag: "muted yellow".to_string(),
+                    line: 1
+                },
+                LintWarning::Unreachable {
+                    bag: "dotted black".to_string(),
+                    line: 7
+                },
+            ],
+            warnings
+        );
+        assert_eq!(
+            "line 5: \"shiny gold\" redefined with different contents than on line 4",
+            warnings[3].to_string()
+        );
+    }
+
+    #[test]
+    fn test_lint_invalid_count() {
+        let text = "light red bags contain 99999999999 bright white bags, 1 muted yellow bag.\n\
+                    muted yellow bags contain no other bags.\n\
+                    bright white bags contain no other bags.";
+        let warnings = lint_rules(text, None);
+        assert_eq!(
+            vec![
+                LintWarning::InvalidCount {
+                    line: 1,
+                    count: "99999999999".to_string()
+                },
+                LintWarning::Unreachable {
+                    bag: "muted yellow".to_string(),
+                    line: 2
+                },
+                LintWarning::Unreachable {
+                    bag: "bright white".to_string(),
+                    line: 3
+                },
+            ],
+            warnings
+        );
+        assert_eq!("line 1: count 99999999999 is too large", warnings[0].to_string());
+    }
+
+    #[test]
+    fn test_lint_unreachable_from_root() {
+        let text = std::fs::read_to_string("data/example.txt").unwrap();
+        let warnings = lint_rules(&text, Some("dark olive"));
+        let unreachable = warnings
+            .iter()
+            .map(|warning| match warning {
+                LintWarning::Unreachable { bag, .. } => bag.as_str(),
+                _ => "",
+            })
+            .collect::<Vec<&str>>();
+        assert_eq!(vec!["vibrant plum"], unreachable);
+        let warnings = lint_rules(&text, Some("plaid magenta"));
+        assert_eq!(
+            vec![LintWarning::UnknownRoot {
+                bag: "plaid magenta".to_string()
+            }],
+            warnings
+        );
+        assert_eq!("root \"plaid magenta\" is not a known bag", warnings[0].to_string());
+    }
+
+    #[test]
+    fn test_to_rules_round_trip() {
+        let text = "light red bags contain 1 bright white bags, 2 muted yellow bag.\n\
+                    bright white bags contain 1 shiny gold bag.";
+        let normalised = BagGraph::new(text).to_rules();
+        assert_eq!(
+            "bright white bags contain 1 shiny gold bag.\n\
+             light red bags contain 1 bright white bag, 2 muted yellow bags.\n",
+            normalised
+        );
+        let text = "shiny gold bags contain no other bags.\n\
+                    faded blue bags contain 1 shiny gold bag.";
+        assert_eq!(
+            "faded blue bags contain 1 shiny gold bag.\n\
+             shiny gold bags contain no other bags.\n",
+            BagGraph::new(text).to_rules()
+        );
+        assert_eq!(normalised, BagGraph::new(&normalised).to_rules());
+        let normalised = normalise_rules("data/bag_rules.txt");
+        assert_eq!(Vec::<LintWarning>::new(), lint_rules(&normalised, None));
+        let mut original = std::fs::read_to_string("data/bag_rules.txt")
+            .unwrap()
+            .lines()
+            .map(|line| line.to_string())
+            .collect::<Vec<String>>();
+        original.sort();
+        assert_eq!(original, normalised.lines().collect::<Vec<&str>>());
+    }
+
+    #[test]
+    fn test_bags_within_budget() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let packings = graph.bags_within_budget(40, None).unwrap();
+        let packing = |bag: &str, cost| Packing {
+            bag: bag.to_string(),
+            cost,
+        };
+        assert_eq!(
+            vec![
+                packing("dotted black", 1),
+                packing("faded blue", 1),
+                packing("dark olive", 8),
+                packing("vibrant plum", 12),
+                packing("shiny gold", 33),
+                packing("bright white", 34),
+            ],
+            packings
+        );
+        assert_eq!(9, graph.bags_within_budget(407, None).unwrap().len());
+        assert_eq!(8, graph.bags_within_budget(406, None).unwrap().len());
+    }
+
+    #[test]
+    fn test_weighted_budget() {
+        let graph = BagGraph::from_file("data/example.txt");
+        let mut weights: HashMap<String, u128> = HashMap::new();
+        assert_eq!(
+            Err(BagError::MissingWeight("faded blue".to_string())),
+            graph.bags_within_budget(40, Some(&weights))
+        );
+        for name in graph.bags_within_budget(1000, None).unwrap() {
+            weights.insert(name.bag, 2);
+        }
+        weights.insert("faded blue".to_string(), 0);
+        let packings = graph.bags_within_budget(20, Some(&weights)).unwrap();
+        assert_eq!(
+            vec![("faded blue", 0), ("dotted black", 2), ("dark olive", 10), ("vibrant plum", 14)],
+            packings
+                .iter()
+                .map(|packing| (packing.bag.as_str(), packing.cost))
+                .collect::<Vec<(&str, u128)>>()
+        );
+    }
+
+    #[test]
+    fn test_best_carrier() {
+        let graph = BagGraph::from_file("data/example.txt");
+        assert_eq!(
+            Ok(Some(Carrier {
+                bag: "bright white".to_string(),
+                cost: 34,
+                carried: 1
+            })),
+            graph.best_carrier("shiny gold", 1000, None)
+        );
+        assert_eq!(Ok(None), graph.best_carrier("shiny gold", 33, None));
+        let mut weights: HashMap<String, u128> = graph
+            .bags_within_budget(1000, None)
+            .unwrap()
+            .into_iter()
+            .map(|packing| (packing.bag, 1))
+            .collect();
+        weights.insert("bright white".to_string(), 100);
+        assert_eq!(
+            Ok(Some(Carrier {
+                bag: "muted yellow".to_string(),
+                cost: 76,
+                carried: 2
+            })),
+            graph.best_carrier("shiny gold", 1000, Some(&weights))
+        );
+        let weights = weights
+            .into_keys()
+            .map(|bag| (bag, 0))
+            .collect::<HashMap<String, u128>>();
+        assert_eq!(
+            Ok(Some(Carrier {
+                bag: "dark orange".to_string(),
+                cost: 0,
+                carried: 11
+            })),
+            graph.best_carrier("shiny gold", 0, Some(&weights))
+        );
+    }
+
+    #[test]
+    fn test_best_carrier_exact() {
+        let graph = BagGraph::new(
+            "big red bags contain 3 shiny gold bags.\n\
+             huge red bags contain 5 shiny gold bags.\n\
+             small red bags contain 1 shiny gold bag.\n\
+             shiny gold bags contain no other bags.",
+        );
+        let mut weights: HashMap<String, u128> = HashMap::new();
+        weights.insert("shiny gold".to_string(), 1 << 100);
+        weights.insert("small red".to_string(), 0);
+        weights.insert("big red".to_string(), 1);
+        weights.insert("huge red".to_string(), 1);
+        assert_eq!(
+            Ok(Some(Carrier {
+                bag: "small red".to_string(),
+                cost: 1 << 100,
+                carried: 1
+            })),
+            graph.best_carrier("shiny gold", u128::MAX, Some(&weights))
+        );
+        weights.insert("shiny gold".to_string(), 1 << 125);
+        weights.insert("small red".to_string(), 1 << 127);
+        assert_eq!(
+            Ok(Some(Carrier {
+                bag: "huge red".to_string(),
+                cost: (5 << 125) + 1,
+                carried: 5
+            })),
+            graph.best_carrier("shiny gold", u128::MAX, Some(&weights))
+        );
+    }
+
+    #[test]
+    fn test_wide_mul() {
+        assert_eq!((0, 6), wide_mul(2, 3));
+        assert_eq!((1, 0), wide_mul(1 << 64, 1 << 64));
+        assert_eq!((u128::MAX - 1, 1), wide_mul(u128::MAX, u128::MAX));
+        assert_eq!((3 << 124, 0), wide_mul(3 << 125, 1 << 127));
     }
 
     #[test]
     fn test_task1() {
         let name = "shiny gold";
-        assert_eq!(335, count_bags_carrying_bag("data/bag_rules.txt", name));
+        assert_eq!(Ok(335), count_bags_carrying_bag("data/bag_rules.txt", name));
     }
 
     #[test]
     fn test_task2() {
-        assert_eq!(2431, count_individual_bags("data/bag_rules.txt", "shiny gold"));
+        assert_eq!(Ok(2431), count_individual_bags("data/bag_rules.txt", "shiny gold"));
     }
 }
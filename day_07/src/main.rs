@@ -1,7 +1,30 @@
+use std::env;
+
 fn main() {
-    let num = day_07::count_bags_carrying_bag("data/bag_rules.txt", "shiny gold");
-    println!("Number of colors which can carry the bag: {}", num);
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if !args.is_empty() {
+        let query = match args.join(" ").parse::<day_07::Query>() {
+            Ok(query) => query,
+            Err(error) => {
+                eprintln!("{}", error);
+                return;
+            }
+        };
+        let graph = day_07::BagGraph::from_file("data/bag_rules.txt");
+        match graph.answer(&query) {
+            Ok(answer) => println!("{}", answer),
+            Err(error) => eprintln!("Could not answer query: {}", error),
+        }
+        return;
+    }
+
+    match day_07::count_bags_carrying_bag("data/bag_rules.txt", "shiny gold") {
+        Ok(num) => println!("Number of colors which can carry the bag: {}", num),
+        Err(error) => eprintln!("Could not count carrying bags: {}", error),
+    }
 
-    let num = day_07::count_individual_bags("data/bag_rules.txt", "shiny gold");
-    println!("Number of individual bags needed: {}", num);
-}
\ No newline at end of file
+    match day_07::count_individual_bags("data/bag_rules.txt", "shiny gold") {
+        Ok(num) => println!("Number of individual bags needed: {}", num),
+        Err(error) => eprintln!("Could not count individual bags: {}", error),
+    }
+}
@@ -0,0 +1,290 @@
+use crate::Instruction;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleErrorKind {
+    UnknownMnemonic(String),
+    MissingArgument,
+    InvalidArgument(String),
+    UnexpectedToken(String),
+    InvalidLabel(String),
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+    LabelNotAllowed { mnemonic: String, label: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub kind: AssembleErrorKind,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            AssembleErrorKind::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic \"{}\"", mnemonic)
+            }
+            AssembleErrorKind::MissingArgument => write!(f, "missing argument"),
+            AssembleErrorKind::InvalidArgument(argument) => {
+                write!(f, "invalid argument \"{}\"", argument)
+            }
+            AssembleErrorKind::UnexpectedToken(token) => write!(f, "unexpected \"{}\"", token),
+            AssembleErrorKind::InvalidLabel(label) => write!(f, "invalid label \"{}\"", label),
+            AssembleErrorKind::DuplicateLabel(label) => {
+                write!(f, "label \"{}\" is already defined", label)
+            }
+            AssembleErrorKind::UndefinedLabel(label) => {
+                write!(f, "label \"{}\" is not defined", label)
+            }
+            AssembleErrorKind::LabelNotAllowed { mnemonic, label } => {
+                write!(f, "{} cannot take label \"{}\" as argument", mnemonic, label)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+struct Statement<'a> {
+    line: usize,
+    mnemonic: &'a str,
+    argument: &'a str,
+}
+
+fn is_label(name: &str) -> bool {
+    let mut characters = name.chars();
+    match characters.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            characters.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+pub fn assemble(text: &str) -> Result<Vec<Instruction>, Vec<AssembleError>> {
+    let mut errors = Vec::new();
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut statements = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line_nr = i + 1;
+        let mut rest = line.split('#').next().unwrap().trim();
+        while let Some(colon) = rest.find(':') {
+            let label = rest[..colon].trim();
+            if !is_label(label) {
+                errors.push(AssembleError {
+                    line: line_nr,
+                    kind: AssembleErrorKind::InvalidLabel(label.to_string()),
+                });
+            } else if labels.insert(label, statements.len()).is_some() {
+                errors.push(AssembleError {
+                    line: line_nr,
+                    kind: AssembleErrorKind::DuplicateLabel(label.to_string()),
+                });
+            }
+            rest = rest[colon + 1..].trim();
+        }
+        let tokens = rest.split_whitespace().collect::<Vec<&str>>();
+        match tokens.as_slice() {
+            [] => (),
+            [_] => errors.push(AssembleError {
+                line: line_nr,
+                kind: AssembleErrorKind::MissingArgument,
+            }),
+            [mnemonic, argument] => statements.push(Statement {
+                line: line_nr,
+                mnemonic,
+                argument,
+            }),
+            [_, _, unexpected, ..] => errors.push(AssembleError {
+                line: line_nr,
+                kind: AssembleErrorKind::UnexpectedToken(unexpected.to_string()),
+            }),
+        }
+    }
+    let mut program = Vec::new();
+    for (address, statement) in statements.iter().enumerate() {
+        match resolve(statement, address, &labels) {
+            Ok(instruction) => program.push(instruction),
+            Err(kind) => errors.push(AssembleError {
+                line: statement.line,
+                kind,
+            }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        errors.sort_by_key(|error| error.line);
+        Err(errors)
+    }
+}
+
+fn resolve(
+    statement: &Statement,
+    address: usize,
+    labels: &HashMap<&str, usize>,
+) -> Result<Instruction, AssembleErrorKind> {
+    let argument = statement.argument;
+    let mnemonic = statement.mnemonic;
+    let takes_label = match Instruction::from_mnemonic(mnemonic, 0) {
+        Some(instruction) => instruction.offset().is_some(),
+        None => return Err(AssembleErrorKind::UnknownMnemonic(mnemonic.to_string())),
+    };
+    let num = if is_label(argument) {
+        if !takes_label {
+            return Err(AssembleErrorKind::LabelNotAllowed {
+                mnemonic: mnemonic.to_string(),
+                label: argument.to_string(),
+            });
+        }
+        match labels.get(argument) {
+            Some(&target) => target as i32 - address as i32,
+            None => return Err(AssembleErrorKind::UndefinedLabel(argument.to_string())),
+        }
+    } else {
+        argument
+            .parse::<i32>()
+            .map_err(|_| AssembleErrorKind::InvalidArgument(argument.to_string()))?
+    };
+    Ok(Instruction::from_mnemonic(mnemonic, num).unwrap())
+}
+
+pub fn disassemble(program: &[Instruction], with_labels: bool) -> String {
+    let mut labels: BTreeMap<usize, String> = BTreeMap::new();
+    if with_labels {
+        let mut targets = program
+            .iter()
+            .enumerate()
+            .filter_map(|(i, instruction)| Some(i as i64 + instruction.offset()? as i64))
+            .filter(|&target| target >= 0 && target <= program.len() as i64)
+            .map(|target| target as usize)
+            .collect::<Vec<usize>>();
+        targets.sort_unstable();
+        targets.dedup();
+        for (i, target) in targets.into_iter().enumerate() {
+            labels.insert(target, format!("L{}", i + 1));
+        }
+    }
+    let mut text = String::new();
+    for (i, instruction) in program.iter().enumerate() {
+        if let Some(label) = labels.get(&i) {
+            text.push_str(&format!("{}:\n", label));
+        }
+        let label = match instruction.offset() {
+            Some(offset) if i as i64 + offset as i64 >= 0 => {
+                labels.get(&((i as i64 + offset as i64) as usize))
+            }
+            _ => None,
+        };
+        match label {
+            Some(label) => text.push_str(&format!("{} {}\n", instruction.mnemonic(), label)),
+            None => text.push_str(&format!("{}\n", instruction)),
+        }
+    }
+    if let Some(label) = labels.get(&program.len()) {
+        text.push_str(&format!("{}:\n", label));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assembler::{assemble, disassemble, AssembleError, AssembleErrorKind};
+    use crate::get_program;
+    use crate::Instruction::{Acc, Jmp, Jnz, Load, Noop};
+    use std::fs;
+
+    #[test]
+    fn test_assemble_labels_and_comments() {
+        let text = "# count to three\n\
+                    \n\
+                    start:  nop 0\n\
+                    loop:\n\
+                    \tacc 1      # increment\n\
+                    \tjmp done\n\
+                    \tjmp loop\n\
+                    done: acc -2\n\
+                    end:";
+        assert_eq!(Ok(vec![Noop(0), Acc(1), Jmp(2), Jmp(-2), Acc(-2)]), assemble(text));
+        assert_eq!(Ok(vec![Jmp(2), Noop(1)]), assemble("jmp end\nnop start\nstart: end:"));
+        assert_eq!(Ok(vec![Load(3), Jnz(-1)]), assemble("top: ld 3\njnz top"));
+        assert_eq!(
+            "line 1: st cannot take label \"top\" as argument",
+            assemble("top: st top").unwrap_err()[0].to_string()
+        );
+    }
+
+    #[test]
+    fn test_assemble_canonical() {
+        let text = fs::read_to_string("data/boot_code.txt").unwrap();
+        assert_eq!(Ok(get_program(&text)), assemble(&text));
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let text = "acc +1 +2\n\
+                    mul 3\n\
+                    loop: jmp\n\
+                    acc loop\n\
+                    loop: jmp nowhere\n\
+                    nop ++1\n\
+                    2x: nop +0";
+        let errors = assemble(text).unwrap_err();
+        let error = |line, kind| AssembleError { line, kind };
+        assert_eq!(
+            vec![
+                error(1, AssembleErrorKind::UnexpectedToken("+2".to_string())),
+                error(2, AssembleErrorKind::UnknownMnemonic("mul".to_string())),
+                error(3, AssembleErrorKind::MissingArgument),
+                error(
+                    4,
+                    AssembleErrorKind::LabelNotAllowed {
+                        mnemonic: "acc".to_string(),
+                        label: "loop".to_string()
+                    }
+                ),
+                error(5, AssembleErrorKind::DuplicateLabel("loop".to_string())),
+                error(5, AssembleErrorKind::UndefinedLabel("nowhere".to_string())),
+                error(6, AssembleErrorKind::InvalidArgument("++1".to_string())),
+                error(7, AssembleErrorKind::InvalidLabel("2x".to_string())),
+            ],
+            errors
+        );
+        assert_eq!("line 5: label \"nowhere\" is not defined", errors[5].to_string());
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let text = fs::read_to_string("data/example.txt").unwrap();
+        let program = get_program(&text);
+        assert_eq!(text.trim(), disassemble(&program, false).trim());
+        assert_eq!(
+            "L1:\n\
+             nop L1\n\
+             L2:\n\
+             acc +1\n\
+             jmp L4\n\
+             L3:\n\
+             acc +3\n\
+             jmp L2\n\
+             acc -99\n\
+             L4:\n\
+             acc +1\n\
+             jmp L3\n\
+             acc +6\n",
+            disassemble(&program, true)
+        );
+        assert_eq!("jmp -3\nnop L1\nL1:\n", disassemble(&[Jmp(-3), Noop(1)], true));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let text = fs::read_to_string("data/boot_code.txt").unwrap();
+        let program = get_program(&text);
+        assert_eq!(Ok(program.clone()), assemble(&disassemble(&program, true)));
+        assert_eq!(Ok(program.clone()), assemble(&disassemble(&program, false)));
+    }
+}
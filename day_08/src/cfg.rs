@@ -0,0 +1,437 @@
+use crate::Instruction::{self, Jgz, Jmp, Jnz, Jz, Noop};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Target {
+    Instruction(usize),
+    Exit,
+    OutOfBounds(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EdgeKind {
+    Taken,
+    Patched,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Edge {
+    pub from: usize,
+    pub to: Target,
+    pub kind: EdgeKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct ControlFlowGraph {
+    program: Vec<Instruction>,
+    successors: Vec<Vec<Edge>>,
+    predecessors: Vec<Vec<Edge>>,
+    exit_predecessors: Vec<Edge>,
+    blocks: Vec<BasicBlock>,
+    block_of: Vec<usize>,
+}
+
+impl ControlFlowGraph {
+    pub fn new(program: &[Instruction]) -> ControlFlowGraph {
+        let len = program.len();
+        let target = |from: usize, offset: i32| {
+            let target = from as i64 + offset as i64;
+            if target == len as i64 {
+                Target::Exit
+            } else if target < 0 || target > len as i64 {
+                Target::OutOfBounds(target)
+            } else {
+                Target::Instruction(target as usize)
+            }
+        };
+        let mut successors = Vec::with_capacity(len);
+        let mut predecessors = vec![Vec::new(); len];
+        let mut exit_predecessors = Vec::new();
+        for (i, instruction) in program.iter().enumerate() {
+            let (taken, patched) = match *instruction {
+                Jmp(num) => (vec![target(i, num)], Some(target(i, 1))),
+                Noop(num) => (vec![target(i, 1)], Some(target(i, num))),
+                Jz(1) | Jnz(1) | Jgz(1) => (vec![target(i, 1)], None),
+                Jz(num) | Jnz(num) | Jgz(num) => (vec![target(i, 1), target(i, num)], None),
+                _ => (vec![target(i, 1)], None),
+            };
+            let mut edges = taken
+                .into_iter()
+                .map(|to| Edge {
+                    from: i,
+                    to,
+                    kind: EdgeKind::Taken,
+                })
+                .collect::<Vec<Edge>>();
+            if let Some(patched) = patched {
+                edges.push(Edge {
+                    from: i,
+                    to: patched,
+                    kind: EdgeKind::Patched,
+                });
+            }
+            for edge in edges.iter() {
+                match edge.to {
+                    Target::Instruction(to) => predecessors[to].push(*edge),
+                    Target::Exit => exit_predecessors.push(*edge),
+                    Target::OutOfBounds(_) => (),
+                }
+            }
+            successors.push(edges);
+        }
+        let mut leaders = BTreeSet::new();
+        if len > 0 {
+            leaders.insert(0);
+        }
+        for (i, edges) in successors.iter().enumerate() {
+            for edge in edges.iter() {
+                if let Target::Instruction(to) = edge.to {
+                    if to != i + 1 {
+                        leaders.insert(to);
+                    }
+                }
+            }
+            if let Jmp(_) | Jz(_) | Jnz(_) | Jgz(_) = program[i] {
+                if i + 1 < len {
+                    leaders.insert(i + 1);
+                }
+            }
+        }
+        let leaders = leaders.into_iter().collect::<Vec<usize>>();
+        let mut blocks = Vec::with_capacity(leaders.len());
+        let mut block_of = vec![0; len];
+        for (b, &start) in leaders.iter().enumerate() {
+            let end = leaders.get(b + 1).copied().unwrap_or(len);
+            for block in block_of[start..end].iter_mut() {
+                *block = b;
+            }
+            blocks.push(BasicBlock { start, end });
+        }
+        ControlFlowGraph {
+            program: program.to_vec(),
+            successors,
+            predecessors,
+            exit_predecessors,
+            blocks,
+            block_of,
+        }
+    }
+
+    pub fn successors(&self, index: usize) -> &[Edge] {
+        &self.successors[index]
+    }
+
+    pub fn predecessors(&self, index: usize) -> &[Edge] {
+        &self.predecessors[index]
+    }
+
+    pub fn exit_predecessors(&self) -> &[Edge] {
+        &self.exit_predecessors
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    pub fn block_of(&self, index: usize) -> usize {
+        self.block_of[index]
+    }
+
+    fn taken(&self, index: usize) -> Vec<usize> {
+        self.successors[index]
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Taken)
+            .filter_map(|edge| match edge.to {
+                Target::Instruction(to) => Some(to),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn patched(&self, index: usize) -> Option<Target> {
+        self.successors[index]
+            .iter()
+            .find(|edge| edge.kind == EdgeKind::Patched)
+            .map(|edge| edge.to)
+    }
+
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.program.len()];
+        let mut stack = if self.program.is_empty() { vec![] } else { vec![0] };
+        while let Some(i) = stack.pop() {
+            if reachable[i] {
+                continue;
+            }
+            reachable[i] = true;
+            stack.extend(self.taken(i).into_iter().filter(|&to| !reachable[to]));
+        }
+        reachable
+    }
+
+    pub fn unreachable(&self) -> Vec<usize> {
+        self.reachable()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, reachable)| !reachable)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn loops(&self) -> Vec<Vec<usize>> {
+        let len = self.program.len();
+        let mut index: Vec<Option<usize>> = vec![None; len];
+        let mut low = vec![0; len];
+        let mut on_stack = vec![false; len];
+        let mut stack = Vec::new();
+        let mut counter = 0;
+        let mut loops = Vec::new();
+        for root in 0..len {
+            if index[root].is_some() {
+                continue;
+            }
+            let mut calls = vec![(root, 0)];
+            while let Some((node, position)) = calls.pop() {
+                if position == 0 {
+                    index[node] = Some(counter);
+                    low[node] = counter;
+                    counter += 1;
+                    stack.push(node);
+                    on_stack[node] = true;
+                }
+                let successors = self.taken(node);
+                if let Some(&next) = successors.get(position) {
+                    calls.push((node, position + 1));
+                    match index[next] {
+                        None => calls.push((next, 0)),
+                        Some(next_index) if on_stack[next] => {
+                            low[node] = low[node].min(next_index)
+                        }
+                        Some(_) => (),
+                    }
+                    continue;
+                }
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[node]);
+                }
+                if Some(low[node]) == index[node] {
+                    let mut component = Vec::new();
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    if component.len() > 1 || successors.contains(&node) {
+                        component.sort_unstable();
+                        loops.push(component);
+                    }
+                }
+            }
+        }
+        loops.sort();
+        loops
+    }
+
+    pub fn reaches_termination(&self) -> Vec<bool> {
+        let mut reaches = vec![false; self.program.len()];
+        let mut stack = self
+            .exit_predecessors
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::Taken)
+            .map(|edge| edge.from)
+            .collect::<Vec<usize>>();
+        while let Some(i) = stack.pop() {
+            if reaches[i] {
+                continue;
+            }
+            reaches[i] = true;
+            for edge in self.predecessors[i].iter() {
+                if edge.kind == EdgeKind::Taken && !reaches[edge.from] {
+                    stack.push(edge.from);
+                }
+            }
+        }
+        reaches
+    }
+
+    pub fn repairs(&self) -> Vec<usize> {
+        let reaches = self.reaches_termination();
+        let reachable = self.reachable();
+        (0..self.program.len())
+            .filter(|&i| reachable[i])
+            .filter(|&i| match self.patched(i) {
+                Some(Target::Exit) => true,
+                Some(Target::Instruction(to)) => reaches[to],
+                _ => false,
+            })
+            .collect()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph cfg {\n    node [shape=box];\n");
+        for (b, block) in self.blocks.iter().enumerate() {
+            let label = (block.start..block.end)
+                .map(|i| format!("{}: {}\\l", i, self.program[i]))
+                .collect::<String>();
+            dot.push_str(&format!("    b{} [label=\"{}\"];\n", b, label));
+        }
+        dot.push_str("    exit [shape=doublecircle];\n");
+        let mut edges = BTreeSet::new();
+        for edges_from in self.successors.iter() {
+            for edge in edges_from.iter() {
+                let from = self.block_of[edge.from];
+                let to = match edge.to {
+                    Target::Instruction(to) => {
+                        if to == edge.from + 1 && self.block_of[to] == from {
+                            continue;
+                        }
+                        format!("b{}", self.block_of[to])
+                    }
+                    Target::Exit => "exit".to_string(),
+                    Target::OutOfBounds(_) => "out_of_bounds".to_string(),
+                };
+                edges.insert((from, to, edge.kind));
+            }
+        }
+        if edges.iter().any(|(_, to, _)| to == "out_of_bounds") {
+            dot.push_str("    out_of_bounds [shape=octagon];\n");
+        }
+        for (from, to, kind) in edges {
+            let style = match kind {
+                EdgeKind::Taken => "",
+                EdgeKind::Patched => " [style=dashed]",
+            };
+            dot.push_str(&format!("    b{} -> {}{};\n", from, to, style));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cfg::{BasicBlock, ControlFlowGraph, Edge, EdgeKind, Target};
+    use crate::get_program;
+    use std::fs;
+
+    fn example() -> ControlFlowGraph {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        ControlFlowGraph::new(&get_program(&program))
+    }
+
+    #[test]
+    fn test_edges() {
+        let cfg = example();
+        assert_eq!(
+            vec![
+                Edge {
+                    from: 2,
+                    to: Target::Instruction(6),
+                    kind: EdgeKind::Taken
+                },
+                Edge {
+                    from: 2,
+                    to: Target::Instruction(3),
+                    kind: EdgeKind::Patched
+                },
+            ],
+            cfg.successors(2)
+        );
+        assert_eq!(
+            vec![
+                Edge {
+                    from: 0,
+                    to: Target::Instruction(1),
+                    kind: EdgeKind::Taken
+                },
+                Edge {
+                    from: 4,
+                    to: Target::Instruction(1),
+                    kind: EdgeKind::Taken
+                },
+            ],
+            cfg.predecessors(1)
+        );
+        assert_eq!(1, cfg.exit_predecessors().len());
+        let cfg = ControlFlowGraph::new(&get_program("jmp -1\nnop +5"));
+        assert_eq!(Target::OutOfBounds(-1), cfg.successors(0)[0].to);
+        assert_eq!(Target::OutOfBounds(6), cfg.successors(1)[1].to);
+    }
+
+    #[test]
+    fn test_blocks() {
+        let cfg = example();
+        let block = |start, end| BasicBlock { start, end };
+        assert_eq!(
+            vec![
+                block(0, 1),
+                block(1, 3),
+                block(3, 5),
+                block(5, 6),
+                block(6, 8),
+                block(8, 9)
+            ],
+            cfg.blocks()
+        );
+        assert_eq!(4, cfg.block_of(7));
+    }
+
+    #[test]
+    fn test_reachability_and_loops() {
+        let cfg = example();
+        assert_eq!(vec![5, 8], cfg.unreachable());
+        assert_eq!(vec![vec![1, 2, 3, 4, 6, 7]], cfg.loops());
+        let reaches = cfg.reaches_termination();
+        assert_eq!(vec![8], (0..9).filter(|&i| reaches[i]).collect::<Vec<usize>>());
+        assert_eq!(vec![7], cfg.repairs());
+        let cfg = ControlFlowGraph::new(&get_program("jmp +0\nacc +1\njmp -1"));
+        assert_eq!(vec![vec![0], vec![1, 2]], cfg.loops());
+        assert_eq!(Vec::<usize>::new(), cfg.repairs());
+    }
+
+    #[test]
+    fn test_conditional_jumps() {
+        let program = get_program("acc +3\nacc -1\njnz -1\njz +2\njmp -4\nnop +0");
+        let cfg = ControlFlowGraph::new(&program);
+        let targets = cfg
+            .successors(2)
+            .iter()
+            .map(|edge| edge.to)
+            .collect::<Vec<Target>>();
+        assert_eq!(vec![Target::Instruction(3), Target::Instruction(1)], targets);
+        assert_eq!(Vec::<usize>::new(), cfg.unreachable());
+        assert_eq!(vec![vec![0, 1, 2, 3, 4]], cfg.loops());
+        assert_eq!(6, cfg.reaches_termination().iter().filter(|&&reaches| reaches).count());
+        let block = |start, end| BasicBlock { start, end };
+        assert_eq!(
+            vec![block(0, 1), block(1, 3), block(3, 4), block(4, 5), block(5, 6)],
+            cfg.blocks()
+        );
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let cfg = ControlFlowGraph::new(&get_program("nop +2\njmp -1\nacc +1\njmp -9"));
+        assert_eq!(
+            "digraph cfg {\n\
+             \x20   node [shape=box];\n\
+             \x20   b0 [label=\"0: nop +2\\l1: jmp -1\\l\"];\n\
+             \x20   b1 [label=\"2: acc +1\\l3: jmp -9\\l\"];\n\
+             \x20   exit [shape=doublecircle];\n\
+             \x20   out_of_bounds [shape=octagon];\n\
+             \x20   b0 -> b0;\n\
+             \x20   b0 -> b1 [style=dashed];\n\
+             \x20   b1 -> exit [style=dashed];\n\
+             \x20   b1 -> out_of_bounds;\n\
+             }\n",
+            cfg.to_dot()
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::repair::Patch;
+use crate::{Flow, Instruction, Operation, Outcome, State};
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Execution {
+    pub outcome: Outcome,
+    pub acc: i32,
+    pub steps: usize,
+}
+
+pub struct CompiledProgram {
+    instructions: Vec<Instruction>,
+    targets: Vec<i64>,
+    conditional: bool,
+}
+
+struct Scratch {
+    visited: Vec<u32>,
+    generation: u32,
+    seen: HashSet<(usize, State)>,
+}
+
+impl Scratch {
+    fn new(len: usize) -> Scratch {
+        Scratch {
+            visited: vec![0; len],
+            generation: 0,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl CompiledProgram {
+    pub fn new(program: &[Instruction]) -> CompiledProgram {
+        CompiledProgram {
+            instructions: program.to_vec(),
+            targets: program
+                .iter()
+                .enumerate()
+                .map(|(i, instruction)| i as i64 + instruction.argument() as i64)
+                .collect(),
+            conditional: program.iter().any(|instruction| instruction.is_conditional()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn run_batch<'a, V>(&self, variants: V, step_limit: Option<usize>) -> Vec<Execution>
+    where
+        V: IntoIterator<Item = &'a [Patch]>,
+    {
+        let mut program = self.instructions.clone();
+        let mut scratch = Scratch::new(self.len());
+        variants
+            .into_iter()
+            .map(|patches| {
+                for patch in patches.iter() {
+                    patch.apply(&mut program);
+                }
+                let execution = self.execute(&program, step_limit, &mut scratch);
+                for patch in patches.iter() {
+                    program[patch.index()] = self.instructions[patch.index()];
+                }
+                execution
+            })
+            .collect()
+    }
+
+    // Opcode semantics come from `Operation::execute`; only the jump targets
+    // are resolved up front, since patches never change an argument.
+    fn execute(
+        &self,
+        program: &[Instruction],
+        step_limit: Option<usize>,
+        scratch: &mut Scratch,
+    ) -> Execution {
+        let len = program.len() as i64;
+        let mut state = State::default();
+        let mut ip = 0;
+        let mut steps = 0;
+        scratch.generation += 1;
+        if scratch.generation == u32::MAX {
+            scratch.visited.iter_mut().for_each(|visited| *visited = 0);
+            scratch.generation = 1;
+        }
+        scratch.seen.clear();
+        let outcome = loop {
+            if ip == program.len() {
+                break Outcome::Terminated { acc: state.acc };
+            }
+            let repeated = if self.conditional {
+                !scratch.seen.insert((ip, state.clone()))
+            } else {
+                scratch.visited[ip] == scratch.generation
+            };
+            if repeated {
+                break Outcome::InfiniteLoop { acc: state.acc, at: ip };
+            }
+            if step_limit.is_some_and(|limit| steps >= limit) {
+                break Outcome::StepLimitExceeded;
+            }
+            scratch.visited[ip] = scratch.generation;
+            steps += 1;
+            let target = match program[ip].execute(&mut state) {
+                Flow::Next => ip as i64 + 1,
+                Flow::Jump(_) => self.targets[ip],
+                Flow::InvalidAddress(address) => break Outcome::InvalidAddress { at: ip, address },
+                Flow::Overflow => break Outcome::AccumulatorOverflow { at: ip },
+            };
+            if target < 0 || target > len {
+                break Outcome::JumpOutOfBounds { from: ip, target };
+            }
+            ip = target as usize;
+        };
+        Execution {
+            outcome,
+            acc: state.acc,
+            steps,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiled::{CompiledProgram, Execution};
+    use crate::repair::Patch;
+    use crate::Instruction::{self, Acc, AccB, Jgz, Jmp, Jnz, Jz, Load, Noop, Store, Swap};
+    use crate::{get_program, Outcome, Vm};
+    use std::fs;
+
+    fn interpret(program: Vec<Instruction>, step_limit: usize) -> Execution {
+        let mut vm = Vm::new(program).with_step_limit(step_limit);
+        let outcome = vm.run();
+        Execution {
+            outcome,
+            acc: vm.acc(),
+            steps: vm.steps(),
+        }
+    }
+
+    fn run(compiled: &CompiledProgram, step_limit: Option<usize>) -> Execution {
+        compiled.run_batch(vec![&[][..]], step_limit)[0]
+    }
+
+    fn random_program(seed: &mut u64, len: usize) -> Vec<Instruction> {
+        let mut next = |modulo: u64| {
+            *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (*seed >> 33) % modulo
+        };
+        (0..len)
+            .map(|_| {
+                let num = next(9) as i32 - 4;
+                let add = match next(16) {
+                    0 => i32::MAX,
+                    1 => i32::MIN,
+                    _ => num,
+                };
+                match next(10) {
+                    0 => Acc(add),
+                    1 => AccB(add),
+                    2 => Swap(0),
+                    3 => Load(num + 2),
+                    4 => Store(num + 2),
+                    5 => Noop(num),
+                    6 => Jmp(num),
+                    7 => Jz(num),
+                    8 => Jnz(num),
+                    _ => Jgz(num),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_interpreter() {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        let program = get_program(&program);
+        let compiled = CompiledProgram::new(&program);
+        assert_eq!(
+            Execution {
+                outcome: Outcome::InfiniteLoop { acc: 5, at: 1 },
+                acc: 5,
+                steps: 7
+            },
+            run(&compiled, None)
+        );
+        assert_eq!(interpret(program.clone(), 4), run(&compiled, Some(4)));
+        assert_eq!(Outcome::Terminated { acc: 0 }, run(&CompiledProgram::new(&[]), None).outcome);
+        let program = get_program("acc +2147483647\nacc +1");
+        let compiled = CompiledProgram::new(&program);
+        assert_eq!(interpret(program, 10), run(&compiled, None));
+        assert_eq!(Outcome::AccumulatorOverflow { at: 1 }, run(&compiled, None).outcome);
+        let mut seed = 8;
+        for len in 1..200 {
+            let program = random_program(&mut seed, len % 12 + 1);
+            let compiled = CompiledProgram::new(&program);
+            assert_eq!(interpret(program, 500), run(&compiled, Some(500)));
+        }
+    }
+
+    #[test]
+    fn test_batch() {
+        let program = fs::read_to_string("data/boot_code.txt").unwrap();
+        let program = get_program(&program);
+        let compiled = CompiledProgram::new(&program);
+        let variants = (0..program.len())
+            .map(|i| vec![Patch::Flip(i), Patch::Delete((i + 7) % program.len())])
+            .collect::<Vec<Vec<Patch>>>();
+        let executions =
+            compiled.run_batch(variants.iter().map(|patches| patches.as_slice()), None);
+        for (patches, execution) in variants.iter().zip(executions) {
+            let mut patched = program.clone();
+            for patch in patches.iter() {
+                patch.apply(&mut patched);
+            }
+            assert_eq!(interpret(patched, usize::MAX), execution);
+        }
+        let flips = (0..program.len())
+            .map(|i| [Patch::Flip(i)])
+            .collect::<Vec<[Patch; 1]>>();
+        let fixes = compiled
+            .run_batch(flips.iter().map(|patches| &patches[..]), None)
+            .into_iter()
+            .filter(|execution| execution.outcome == Outcome::Terminated { acc: 920 })
+            .count();
+        assert_eq!(1, fixes);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::{address, get_program, Instruction, Outcome, State, Vm};
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    Line(usize),
+    Opcode(String),
+}
+
+impl Breakpoint {
+    fn matches(&self, line: usize, instruction: &Instruction) -> bool {
+        match self {
+            Breakpoint::Line(breakpoint_line) => *breakpoint_line == line,
+            Breakpoint::Opcode(mnemonic) => mnemonic == instruction.mnemonic(),
+        }
+    }
+}
+
+struct Undo {
+    ip: usize,
+    steps: usize,
+    acc: i32,
+    b: i32,
+    memory: Option<(usize, i32)>,
+    seen: Option<(usize, State)>,
+}
+
+impl Undo {
+    fn step(vm: &mut Vm) -> Undo {
+        let memory = match vm.program[vm.ip] {
+            Instruction::Store(num) => address(num).map(|cell| (cell, vm.state.memory[cell])),
+            _ => None,
+        };
+        let mut undo = Undo {
+            ip: vm.ip,
+            steps: vm.steps,
+            acc: vm.state.acc,
+            b: vm.state.b,
+            memory,
+            seen: vm.seen.as_ref().map(|_| (vm.ip, vm.state.clone())),
+        };
+        if let Some(Outcome::InfiniteLoop { .. }) = vm.step() {
+            undo.seen = None;
+        }
+        undo
+    }
+
+    fn restore(self, vm: &mut Vm) {
+        if vm.steps > self.steps {
+            vm.visits[self.ip] -= 1;
+        }
+        if let (Some(seen), Some(key)) = (vm.seen.as_mut(), self.seen) {
+            seen.remove(&key);
+        }
+        if let Some((address, value)) = self.memory {
+            vm.state.memory[address] = value;
+        }
+        vm.state.acc = self.acc;
+        vm.state.b = self.b;
+        vm.ip = self.ip;
+        vm.steps = self.steps;
+        vm.outcome = None;
+    }
+}
+
+pub struct Debugger {
+    vm: Vm,
+    history: Vec<Undo>,
+    breakpoints: Vec<Option<Breakpoint>>,
+    watch_acc: bool,
+}
+
+impl Debugger {
+    pub fn new(program: Vec<Instruction>) -> Debugger {
+        Debugger {
+            vm: Vm::new(program),
+            history: Vec::new(),
+            breakpoints: Vec::new(),
+            watch_acc: false,
+        }
+    }
+
+    pub fn vm(&self) -> &Vm {
+        &self.vm
+    }
+
+    pub fn execute(&mut self, command: &str) -> String {
+        let words = command.split_whitespace().collect::<Vec<&str>>();
+        match words.as_slice() {
+            ["break", target] | ["b", target] => self.add_breakpoint(target),
+            ["delete", id] | ["d", id] => self.delete_breakpoint(id),
+            ["watch"] | ["w"] => {
+                self.watch_acc = true;
+                "Watching acc".to_string()
+            }
+            ["unwatch"] => {
+                self.watch_acc = false;
+                "Stopped watching acc".to_string()
+            }
+            ["step"] | ["s"] => self.step(1),
+            ["step", count] | ["s", count] => match count.parse() {
+                Ok(count) => self.step(count),
+                Err(_) => format!("Invalid step count: {}", count),
+            },
+            ["back"] => self.back(1),
+            ["back", count] => match count.parse() {
+                Ok(count) => self.back(count),
+                Err(_) => format!("Invalid step count: {}", count),
+            },
+            ["continue"] | ["c"] => self.resume(),
+            ["print"] | ["p"] => self.location(),
+            ["visits"] => self.visits(),
+            [] => String::new(),
+            _ => format!("Unknown command: {}", command.trim()),
+        }
+    }
+
+    pub fn run_script<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let command = line.trim();
+            if command == "quit" || command == "q" {
+                break;
+            }
+            let response = self.execute(command);
+            if !response.is_empty() {
+                writeln!(output, "{}", response)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_breakpoint(&mut self, target: &str) -> String {
+        let breakpoint = match target.parse::<usize>() {
+            Ok(line) if line >= 1 && line <= self.vm.program().len() => Breakpoint::Line(line),
+            Ok(line) => return format!("No line {} in program", line),
+            Err(_) if Instruction::from_mnemonic(target, 0).is_some() => {
+                Breakpoint::Opcode(target.to_string())
+            }
+            Err(_) => return format!("Invalid breakpoint: {}", target),
+        };
+        let description = match &breakpoint {
+            Breakpoint::Line(line) => format!("line {}", line),
+            Breakpoint::Opcode(mnemonic) => mnemonic.to_string(),
+        };
+        self.breakpoints.push(Some(breakpoint));
+        format!("Breakpoint {} at {}", self.breakpoints.len(), description)
+    }
+
+    fn delete_breakpoint(&mut self, id: &str) -> String {
+        let index = id.parse::<usize>().ok().and_then(|id| id.checked_sub(1));
+        match index.and_then(|index| self.breakpoints.get_mut(index)) {
+            Some(breakpoint) if breakpoint.is_some() => {
+                *breakpoint = None;
+                format!("Deleted breakpoint {}", id)
+            }
+            _ => format!("No breakpoint {}", id),
+        }
+    }
+
+    fn at_breakpoint(&self) -> Option<usize> {
+        let line = self.vm.ip() + 1;
+        let instruction = self.vm.program().get(self.vm.ip())?;
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| match breakpoint {
+                Some(breakpoint) => breakpoint.matches(line, instruction),
+                None => false,
+            })
+            .map(|index| index + 1)
+    }
+
+    fn single_step(&mut self) -> Option<String> {
+        if self.vm.outcome().is_some() {
+            return Some(self.location());
+        }
+        let acc = self.vm.acc();
+        let undo = Undo::step(&mut self.vm);
+        self.history.push(undo);
+        if self.watch_acc && self.vm.acc() != acc {
+            return Some(format!(
+                "acc changed from {} to {}\n{}",
+                acc,
+                self.vm.acc(),
+                self.location()
+            ));
+        }
+        if self.vm.outcome().is_some() {
+            return Some(self.location());
+        }
+        None
+    }
+
+    fn step(&mut self, count: usize) -> String {
+        for _ in 0..count {
+            if let Some(message) = self.single_step() {
+                return message;
+            }
+        }
+        self.location()
+    }
+
+    fn resume(&mut self) -> String {
+        loop {
+            if let Some(message) = self.single_step() {
+                return message;
+            }
+            if let Some(id) = self.at_breakpoint() {
+                return format!("Breakpoint {}\n{}", id, self.location());
+            }
+        }
+    }
+
+    fn back(&mut self, count: usize) -> String {
+        for _ in 0..count {
+            match self.history.pop() {
+                Some(undo) => undo.restore(&mut self.vm),
+                None => return format!("At start of history\n{}", self.location()),
+            }
+        }
+        self.location()
+    }
+
+    fn location(&self) -> String {
+        match self.vm.outcome() {
+            Some(Outcome::Terminated { acc }) => format!("Program terminated with acc {}", acc),
+            Some(Outcome::InfiniteLoop { acc, at }) => format!(
+                "Infinite loop: line {} about to run a second time, acc {}",
+                at + 1,
+                acc
+            ),
+            Some(Outcome::JumpOutOfBounds { from, target }) => format!(
+                "Jump out of bounds from line {} to line {}",
+                from + 1,
+                target + 1
+            ),
+            Some(Outcome::InvalidAddress { at, address }) => format!(
+                "Invalid memory address {} at line {}",
+                address,
+                at + 1
+            ),
+            Some(Outcome::AccumulatorOverflow { at }) => {
+                format!("Accumulator overflow at line {}", at + 1)
+            }
+            Some(Outcome::StepLimitExceeded) => "Step limit exceeded".to_string(),
+            None => format!(
+                "line {}: {} (acc {}, step {})",
+                self.vm.ip() + 1,
+                self.vm.program()[self.vm.ip()],
+                self.vm.acc(),
+                self.vm.steps()
+            ),
+        }
+    }
+
+    fn visits(&self) -> String {
+        self.vm
+            .program()
+            .iter()
+            .enumerate()
+            .map(|(i, instruction)| {
+                format!("{:>4}: {:<8} {}", i + 1, instruction.to_string(), self.vm.visit_count(i))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+pub fn debug_program<R: BufRead, W: Write>(program: &str, input: R, output: W) -> io::Result<()> {
+    Debugger::new(get_program(program)).run_script(input, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::debugger::{debug_program, Debugger};
+    use crate::{get_program, Vm};
+    use std::fs;
+
+    fn run(commands: &str) -> String {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        let mut output = Vec::new();
+        debug_program(&program, commands.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_step_and_back() {
+        assert_eq!(
+            "line 1: nop +0 (acc 0, step 0)\n\
+             line 3: jmp +4 (acc 1, step 2)\n\
+             line 7: acc +1 (acc 1, step 3)\n\
+             line 2: acc +1 (acc 0, step 1)\n\
+             At start of history\n\
+             line 1: nop +0 (acc 0, step 0)\n",
+            run("print\nstep 2\nstep\nback 2\nback 5\n")
+        );
+    }
+
+    #[test]
+    fn test_back_restores_vm() {
+        let program = get_program("acc +2\nst +3\nacc -1\njnz -2\nld +3\nswp +0\naccb -1\njmp -3");
+        let expected = Vm::new(program.clone()).run();
+        let mut debugger = Debugger::new(program.clone());
+        for count in 1..20 {
+            debugger.step(count);
+            debugger.back(count);
+            let fresh = Vm::new(program.clone());
+            let vm = debugger.vm();
+            assert_eq!(
+                (fresh.ip(), fresh.steps(), fresh.state()),
+                (vm.ip(), vm.steps(), vm.state())
+            );
+            assert_eq!(None, vm.outcome());
+            assert!((0..program.len()).all(|i| vm.visit_count(i) == 0));
+        }
+        debugger.step(12);
+        debugger.back(5);
+        debugger.resume();
+        assert_eq!(Some(expected), debugger.vm().outcome());
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        assert_eq!(
+            "Breakpoint 1 at line 4\n\
+             Breakpoint 2 at jmp\n\
+             Breakpoint 2\n\
+             line 3: jmp +4 (acc 1, step 2)\n\
+             Deleted breakpoint 2\n\
+             Breakpoint 1\n\
+             line 4: acc +3 (acc 2, step 5)\n\
+             Infinite loop: line 2 about to run a second time, acc 5\n",
+            run("break 4\nbreak jmp\ncontinue\ndelete 2\nc\nc\n")
+        );
+    }
+
+    #[test]
+    fn test_watch_and_visits() {
+        let output = run("watch\ncontinue\ncontinue\nunwatch\ncontinue\nvisits\nquit\nprint\n");
+        let lines = output.lines().collect::<Vec<&str>>();
+        assert_eq!("Watching acc", lines[0]);
+        assert_eq!("acc changed from 0 to 1", lines[1]);
+        assert_eq!("line 3: jmp +4 (acc 1, step 2)", lines[2]);
+        assert_eq!("acc changed from 1 to 2", lines[3]);
+        assert_eq!("line 8: jmp -4 (acc 2, step 4)", lines[4]);
+        assert_eq!("Stopped watching acc", lines[5]);
+        assert_eq!("Infinite loop: line 2 about to run a second time, acc 5", lines[6]);
+        assert_eq!("   1: nop +0   1", lines[7]);
+        assert_eq!("   6: acc -99  0", lines[12]);
+        assert_eq!(16, lines.len());
+    }
+
+    #[test]
+    fn test_conditional_opcode_breakpoints() {
+        let mut debugger = Debugger::new(get_program("acc +2\nst +0\nacc -1\njnz -1\nld +0"));
+        assert_eq!("Breakpoint 1 at jnz", debugger.execute("break jnz"));
+        assert_eq!("Breakpoint 2 at ld", debugger.execute("b ld"));
+        assert_eq!("Breakpoint 1\nline 4: jnz -1 (acc 1, step 3)", debugger.execute("c"));
+        assert_eq!("Breakpoint 1\nline 4: jnz -1 (acc 0, step 5)", debugger.execute("c"));
+        assert_eq!("Breakpoint 2\nline 5: ld +0 (acc 0, step 6)", debugger.execute("c"));
+        assert_eq!("Program terminated with acc 2", debugger.execute("c"));
+    }
+
+    #[test]
+    fn test_invalid_commands() {
+        let mut debugger = Debugger::new(get_program("nop +0\njmp +1"));
+        assert_eq!("No line 3 in program", debugger.execute("break 3"));
+        assert_eq!("Invalid breakpoint: mul", debugger.execute("break mul"));
+        assert_eq!("No breakpoint 1", debugger.execute("delete 1"));
+        assert_eq!("Unknown command: jump 3", debugger.execute("jump 3"));
+        assert_eq!("Program terminated with acc 0", debugger.execute("continue"));
+        assert_eq!(2, debugger.vm().steps());
+    }
+}
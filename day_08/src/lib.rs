@@ -1,179 +1,436 @@
 use Instruction::*;
+use std::collections::HashSet;
+use std::fmt;
 use std::fs;
 
-#[derive(Debug, PartialEq)]
-enum Instruction {
+pub mod assembler;
+pub mod cfg;
+pub mod compiled;
+pub mod debugger;
+pub mod repair;
+pub mod trace;
+
+use assembler::{AssembleError, AssembleErrorKind};
+use cfg::ControlFlowGraph;
+use compiled::CompiledProgram;
+use repair::{Patch, Repair, RepairSearch};
+use trace::Trace;
+
+pub const MEMORY_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
     Noop(i32),
     Acc(i32),
     Jmp(i32),
+    Jz(i32),
+    Jnz(i32),
+    Jgz(i32),
+    AccB(i32),
+    Swap(i32),
+    Load(i32),
+    Store(i32),
 }
 
-struct CodeLine {
-    instruction: Instruction,
-    visited: bool,
+impl Instruction {
+    pub fn flipped(&self) -> Instruction {
+        match *self {
+            Noop(num) => Jmp(num),
+            Jmp(num) => Noop(num),
+            other => other,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Noop(_) => "nop",
+            Acc(_) => "acc",
+            Jmp(_) => "jmp",
+            Jz(_) => "jz",
+            Jnz(_) => "jnz",
+            Jgz(_) => "jgz",
+            AccB(_) => "accb",
+            Swap(_) => "swp",
+            Load(_) => "ld",
+            Store(_) => "st",
+        }
+    }
+
+    pub fn from_mnemonic(mnemonic: &str, num: i32) -> Option<Instruction> {
+        match mnemonic {
+            "nop" => Some(Noop(num)),
+            "acc" => Some(Acc(num)),
+            "jmp" => Some(Jmp(num)),
+            "jz" => Some(Jz(num)),
+            "jnz" => Some(Jnz(num)),
+            "jgz" => Some(Jgz(num)),
+            "accb" => Some(AccB(num)),
+            "swp" => Some(Swap(num)),
+            "ld" => Some(Load(num)),
+            "st" => Some(Store(num)),
+            _ => None,
+        }
+    }
+
+    pub fn argument(&self) -> i32 {
+        match *self {
+            Noop(num) | Acc(num) | Jmp(num) | Jz(num) | Jnz(num) | Jgz(num) | AccB(num)
+            | Swap(num) | Load(num) | Store(num) => num,
+        }
+    }
+
+    pub fn offset(&self) -> Option<i32> {
+        match *self {
+            Noop(num) | Jmp(num) | Jz(num) | Jnz(num) | Jgz(num) => Some(num),
+            Acc(_) | AccB(_) | Swap(_) | Load(_) | Store(_) => None,
+        }
+    }
 }
 
-struct CodeBranch {
-    instruction: Instruction,
-    next: usize,
-    possible_next: usize,
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:+}", self.mnemonic(), self.argument())
+    }
 }
 
-fn get_instruction(line: &str) -> Instruction {
-    let re = regex::Regex::new(r"^(acc|jmp|nop) ((\+|-)\d+)$").unwrap();
-    let cap = re.captures(line).unwrap();
-    let num = cap[2].parse().unwrap();
-    match &cap[1] {
-        "acc" => Acc(num),
-        "jmp" => Jmp(num),
-        "nop" => Noop(num),
-        _ => panic!(),
-    }
-}
-
-fn get_program(program: &str) -> Vec<CodeLine> {
-    program.lines().map(|line| {
-        let instruction = get_instruction(line);
-        CodeLine {
-            instruction,
-            visited: false,
-        }
-    }).collect()
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct State {
+    pub acc: i32,
+    pub b: i32,
+    pub memory: [i32; MEMORY_SIZE],
 }
 
-pub fn acc_at_loop(program: &str) -> i32 {
-    let mut code = get_program(program);
-    let mut acc = 0;
-    let mut next_instruction: Option<usize> = Some(0);
-    while next_instruction.is_some() {
-        let index = next_instruction.unwrap();
-        let mut current_code = code.get_mut(index).unwrap();
-        if current_code.visited {
-            next_instruction = None;
-        } else {
-            match current_code.instruction {
-                Acc(num) => {
-                    acc += num;
-                    next_instruction = Some(index + 1);
-                },
-                Jmp(num) => next_instruction = Some((index as i32 + num) as usize),
-                Noop(_) => next_instruction = Some(index + 1),
-            };
-            current_code.visited = true;
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Next,
+    Jump(i32),
+    InvalidAddress(i32),
+    Overflow,
+}
+
+pub trait Operation {
+    fn execute(&self, state: &mut State) -> Flow;
+
+    fn is_conditional(&self) -> bool {
+        false
     }
-    acc
 }
 
-pub fn get_acc_of_program(filename: &str) -> i32 {
-    let program = fs::read_to_string(filename).unwrap();
-    acc_at_loop(&program)
+fn address(num: i32) -> Option<usize> {
+    if num >= 0 && (num as usize) < MEMORY_SIZE {
+        Some(num as usize)
+    } else {
+        None
+    }
 }
 
-fn get_code_branch(program: &str) -> Vec<CodeBranch> {
-    program.lines().enumerate().map(|(i, line)| {
-        let instruction = get_instruction(line);
-        let (next, possible_next) = match instruction {
-            Acc(_) => (i + 1, i + 1),
-            Jmp(num) => ((i as i32 + num) as usize, i + 1),
-            Noop(num) => (i + 1, (i as i32 + num) as usize),
-        };
-        CodeBranch {
-            instruction,
-            next,
-            possible_next,
+impl Operation for Instruction {
+    fn execute(&self, state: &mut State) -> Flow {
+        match *self {
+            Acc(num) => match state.acc.checked_add(num) {
+                Some(acc) => {
+                    state.acc = acc;
+                    Flow::Next
+                }
+                None => Flow::Overflow,
+            },
+            Jmp(num) => Flow::Jump(num),
+            Noop(_) => Flow::Next,
+            Jz(num) if state.acc == 0 => Flow::Jump(num),
+            Jnz(num) if state.acc != 0 => Flow::Jump(num),
+            Jgz(num) if state.acc > 0 => Flow::Jump(num),
+            Jz(_) | Jnz(_) | Jgz(_) => Flow::Next,
+            AccB(num) => match state.b.checked_add(num) {
+                Some(b) => {
+                    state.b = b;
+                    Flow::Next
+                }
+                None => Flow::Overflow,
+            },
+            Swap(_) => {
+                std::mem::swap(&mut state.acc, &mut state.b);
+                Flow::Next
+            }
+            Load(num) => match address(num) {
+                Some(address) => {
+                    state.acc = state.memory[address];
+                    Flow::Next
+                }
+                None => Flow::InvalidAddress(num),
+            },
+            Store(num) => match address(num) {
+                Some(address) => {
+                    state.memory[address] = state.acc;
+                    Flow::Next
+                }
+                None => Flow::InvalidAddress(num),
+            },
         }
-    }).collect()
+    }
+
+    fn is_conditional(&self) -> bool {
+        matches!(self, Jz(_) | Jnz(_) | Jgz(_))
+    }
 }
 
-fn get_partition_with_start(branches: &Vec<CodeBranch>, index: usize) -> Vec<usize> {
-    let mut next = Some(index);
-    let mut partition = Vec::new();
-    while let Some(i) = next {
-        if i >= branches.len() {
-            break;
-        }
-        let branch = &branches[i];
-        if partition.contains(&i) {
-            next = None;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Terminated { acc: i32 },
+    InfiniteLoop { acc: i32, at: usize },
+    JumpOutOfBounds { from: usize, target: i64 },
+    InvalidAddress { at: usize, address: i32 },
+    AccumulatorOverflow { at: usize },
+    StepLimitExceeded,
+}
+
+#[derive(Debug, Clone)]
+pub struct Vm<I = Instruction> {
+    program: Vec<I>,
+    state: State,
+    ip: usize,
+    steps: usize,
+    step_limit: Option<usize>,
+    visits: Vec<usize>,
+    seen: Option<HashSet<(usize, State)>>,
+    outcome: Option<Outcome>,
+}
+
+impl<I: Operation> Vm<I> {
+    pub fn new(program: Vec<I>) -> Vm<I> {
+        let visits = vec![0; program.len()];
+        let seen = if program.iter().any(|instruction| instruction.is_conditional()) {
+            Some(HashSet::new())
         } else {
-            partition.push(i);
-            next = Some(branch.next);
-        }
-    };
-    partition
-}
-
-fn divide_into_partitions(branches: &Vec<CodeBranch>) -> Vec<Vec<usize>> {
-    let mut all_visited = Vec::new();
-    let mut partitions = Vec::new();
-    for i in 0..branches.len() {
-        if !all_visited.contains(&i) {
-            let partition = get_partition_with_start(branches, i);
-            all_visited.append(&mut partition.clone());
-            partitions.push(partition);
+            None
+        };
+        let outcome = if program.is_empty() {
+            Some(Outcome::Terminated { acc: 0 })
+        } else {
+            None
+        };
+        Vm {
+            program,
+            state: State::default(),
+            ip: 0,
+            steps: 0,
+            step_limit: None,
+            visits,
+            seen,
+            outcome,
         }
     }
-    partitions
-}
 
-fn find_instruction_to_change(branches: &Vec<CodeBranch>, partitions: Vec<Vec<usize>>) -> usize {
-    let start_partition = partitions.iter().fold(Vec::new(), |acc, partition| {
-        if partition.contains(&0) {
-            partition.clone()
+    pub fn with_step_limit(mut self, step_limit: usize) -> Vm<I> {
+        self.step_limit = Some(step_limit);
+        self
+    }
+
+    pub fn acc(&self) -> i32 {
+        self.state.acc
+    }
+
+    pub fn ip(&self) -> usize {
+        self.ip
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn program(&self) -> &[I] {
+        &self.program
+    }
+
+    pub fn visit_count(&self, index: usize) -> usize {
+        self.visits.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn step(&mut self) -> Option<Outcome> {
+        if self.outcome.is_some() {
+            return self.outcome;
+        }
+        let repeated = match &mut self.seen {
+            Some(seen) => !seen.insert((self.ip, self.state.clone())),
+            None => self.visits[self.ip] > 0,
+        };
+        if repeated {
+            self.outcome = Some(Outcome::InfiniteLoop {
+                acc: self.state.acc,
+                at: self.ip,
+            });
+            return self.outcome;
+        }
+        if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+            self.outcome = Some(Outcome::StepLimitExceeded);
+            return self.outcome;
+        }
+        self.visits[self.ip] += 1;
+        self.steps += 1;
+        let target = match self.program[self.ip].execute(&mut self.state) {
+            Flow::Next => self.ip as i64 + 1,
+            Flow::Jump(offset) => self.ip as i64 + offset as i64,
+            Flow::InvalidAddress(address) => {
+                self.outcome = Some(Outcome::InvalidAddress {
+                    at: self.ip,
+                    address,
+                });
+                return self.outcome;
+            }
+            Flow::Overflow => {
+                self.outcome = Some(Outcome::AccumulatorOverflow { at: self.ip });
+                return self.outcome;
+            }
+        };
+        let len = self.program.len() as i64;
+        if target == len {
+            self.ip = self.program.len();
+            self.outcome = Some(Outcome::Terminated {
+                acc: self.state.acc,
+            });
+        } else if target < 0 || target > len {
+            self.outcome = Some(Outcome::JumpOutOfBounds {
+                from: self.ip,
+                target,
+            });
         } else {
-            acc
+            self.ip = target as usize;
         }
-    });
-    let end_partitions: Vec<Vec<usize>> = partitions.iter().cloned().filter(|elem| elem.contains(&(branches.len() - 1))).collect();
-    let mut index= 0;
-    for i in start_partition.iter() {
-        let mut points_to_end = false;
-        let branch = &branches[*i];
-        for partition in end_partitions.iter() {
-            if partition.contains(&branch.possible_next) {
-                points_to_end = true;
-                break;
+        self.outcome
+    }
+
+    pub fn run(&mut self) -> Outcome {
+        loop {
+            if let Some(outcome) = self.step() {
+                return outcome;
             }
         }
-        if points_to_end {
-            index = *i;
-            break;
+    }
+
+    pub fn run_until<P>(&mut self, mut predicate: P) -> Option<Outcome>
+    where
+        P: FnMut(&Vm<I>) -> bool,
+    {
+        while self.outcome.is_none() && !predicate(self) {
+            self.step();
         }
+        self.outcome
     }
-    index
 }
 
-pub fn get_acc_at_end(filename: &str) -> i32 {
+fn parse_instruction(line: &str) -> Result<Instruction, AssembleErrorKind> {
+    let re = regex::Regex::new(r"^([a-z]+) ((\+|-)\d+)$").unwrap();
+    let cap = re
+        .captures(line)
+        .ok_or_else(|| AssembleErrorKind::UnexpectedToken(line.to_string()))?;
+    let num = cap[2]
+        .parse()
+        .map_err(|_| AssembleErrorKind::InvalidArgument(cap[2].to_string()))?;
+    Instruction::from_mnemonic(&cap[1], num)
+        .ok_or_else(|| AssembleErrorKind::UnknownMnemonic(cap[1].to_string()))
+}
+
+fn get_instruction(line: &str) -> Instruction {
+    parse_instruction(line).unwrap()
+}
+
+pub fn get_program(program: &str) -> Vec<Instruction> {
+    program.lines().map(get_instruction).collect()
+}
+
+pub fn parse_program(program: &str) -> Result<Vec<Instruction>, AssembleError> {
+    program
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            parse_instruction(line).map_err(|kind| AssembleError { line: i + 1, kind })
+        })
+        .collect()
+}
+
+pub fn acc_at_loop(program: &str) -> i32 {
+    let mut vm = Vm::new(get_program(program));
+    vm.run();
+    vm.acc()
+}
+
+pub fn trace_at_loop(program: &str) -> Trace {
+    Trace::record(&mut Vm::new(get_program(program)))
+}
+
+pub fn run_boot_code(program: &str, step_limit: usize) -> Result<Outcome, AssembleError> {
+    Ok(Vm::new(parse_program(program)?)
+        .with_step_limit(step_limit)
+        .run())
+}
+
+pub fn get_acc_of_program(filename: &str) -> i32 {
     let program = fs::read_to_string(filename).unwrap();
-    let code = get_code_branch(&program);
-    let partitions = divide_into_partitions(&code);
-    let index_to_change = find_instruction_to_change(&code, partitions);
-    let mut acc = 0;
-    let mut next_instruction = Some(0);
-    while let Some(i) = next_instruction {
-        let mut next = code[i].next;
-        if i == index_to_change {
-            next = code[i].possible_next;
-        }
-        if next < code.len() {
-            next_instruction = Some(next);
-        } else {
-            next_instruction = None;
-        }
-        acc = if let Acc(num) = code[i].instruction {
-            acc + num
-        } else {
-            acc
-        }
+    acc_at_loop(&program)
+}
+
+fn find_instruction_to_change(program: &[Instruction]) -> Vec<usize> {
+    ControlFlowGraph::new(program).repairs()
+}
+
+fn repaired_program(program: &str) -> Option<Vec<Instruction>> {
+    let mut program = get_program(program);
+    let candidates = find_instruction_to_change(&program)
+        .into_iter()
+        .map(|index| [Patch::Flip(index)])
+        .collect::<Vec<[Patch; 1]>>();
+    let executions = CompiledProgram::new(&program)
+        .run_batch(candidates.iter().map(|patches| &patches[..]), None);
+    let (patches, _) = candidates
+        .iter()
+        .zip(executions)
+        .find(|(_, execution)| matches!(execution.outcome, Outcome::Terminated { .. }))?;
+    patches[0].apply(&mut program);
+    Some(program)
+}
+
+pub fn trace_at_end(program: &str) -> Option<Trace> {
+    Some(Trace::record(&mut Vm::new(repaired_program(program)?)))
+}
+
+pub fn get_acc_at_end(filename: &str) -> Option<i32> {
+    let program = fs::read_to_string(filename).unwrap();
+    match Vm::new(repaired_program(&program)?).run() {
+        Outcome::Terminated { acc } => Some(acc),
+        _ => None,
     }
-    acc
+}
+
+pub fn get_trace_of_program(filename: &str) -> Trace {
+    let program = fs::read_to_string(filename).unwrap();
+    trace_at_loop(&program)
+}
+
+pub fn get_trace_at_end(filename: &str) -> Option<Trace> {
+    let program = fs::read_to_string(filename).unwrap();
+    trace_at_end(&program)
+}
+
+pub fn get_repairs(filename: &str, max_changes: usize, deletions: bool) -> Vec<Repair> {
+    let program = fs::read_to_string(filename).unwrap();
+    RepairSearch::new(&get_program(&program))
+        .with_max_changes(max_changes)
+        .with_deletions(deletions)
+        .repairs()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{get_instruction, get_program, acc_at_loop, get_code_branch, divide_into_partitions, find_instruction_to_change, get_acc_at_end, get_acc_of_program};
-    use crate::Instruction::{Acc, Jmp, Noop};
+    use crate::{get_instruction, get_program, acc_at_loop, find_instruction_to_change, get_acc_at_end, get_acc_of_program, get_repairs, get_trace_at_end, get_trace_of_program, parse_program, run_boot_code, Flow, Instruction, Operation, Outcome, State, Vm};
+    use crate::assembler::{AssembleError, AssembleErrorKind};
+    use crate::Instruction::{Acc, AccB, Jgz, Jmp, Jnz, Noop, Store};
     use std::fs;
 
     #[test]
@@ -194,9 +451,9 @@ mod tests {
     #[test]
     fn test_get_program() {
         let program = fs::read_to_string("data/example.txt").unwrap();
-        assert_eq!(Noop(0), get_program(&program)[0].instruction);
-        assert_eq!(Acc(6), get_program(&program)[8].instruction);
-        assert!(!get_program(&program)[0].visited);
+        assert_eq!(Noop(0), get_program(&program)[0]);
+        assert_eq!(Acc(6), get_program(&program)[8]);
+        assert_eq!(0, Vm::new(get_program(&program)).visit_count(0));
     }
 
     #[test]
@@ -208,14 +465,178 @@ mod tests {
     #[test]
     fn test_find_instruction_to_change() {
         let program = fs::read_to_string("data/example.txt").unwrap();
-        let branches = get_code_branch(&program);
-        let partitions = divide_into_partitions(&branches);
-        assert_eq!(7, find_instruction_to_change(&branches, partitions));
+        assert_eq!(vec![7], find_instruction_to_change(&get_program(&program)));
+        assert_eq!(
+            Vec::<usize>::new(),
+            find_instruction_to_change(&get_program("jmp +0\njmp +0\nacc +1"))
+        );
     }
 
     #[test]
     fn test_get_acc_at_end() {
-        assert_eq!(8, get_acc_at_end("data/example.txt"));
+        assert_eq!(Some(8), get_acc_at_end("data/example.txt"));
+    }
+
+    #[test]
+    fn test_traces() {
+        let trace = get_trace_of_program("data/example.txt");
+        assert_eq!(Outcome::InfiniteLoop { acc: 5, at: 1 }, trace.outcome);
+        assert_eq!(7, trace.summary().steps);
+        let trace = get_trace_at_end("data/example.txt").unwrap();
+        assert_eq!(Outcome::Terminated { acc: 8 }, trace.outcome);
+        assert_eq!(None, trace.summary().first_repeated_ip);
+        assert_eq!(9, trace.steps.last().unwrap().next_ip);
+    }
+
+    #[test]
+    fn test_vm_step() {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        let mut vm = Vm::new(get_program(&program));
+        assert_eq!(None, vm.step());
+        assert_eq!(None, vm.step());
+        assert_eq!((1, 2, 2), (vm.acc(), vm.ip(), vm.steps()));
+        assert_eq!(None, vm.step());
+        assert_eq!(6, vm.ip());
+        assert_eq!(Outcome::InfiniteLoop { acc: 5, at: 1 }, vm.run());
+        assert_eq!(1, vm.ip());
+        assert_eq!(7, vm.steps());
+        assert_eq!(1, vm.visit_count(1));
+        assert_eq!(0, vm.visit_count(5));
+    }
+
+    #[test]
+    fn test_vm_run_until() {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        let mut vm = Vm::new(get_program(&program));
+        assert_eq!(None, vm.run_until(|vm| vm.acc() >= 4));
+        assert_eq!((5, 4), (vm.acc(), vm.ip()));
+        let mut program = get_program(&program);
+        program[7] = program[7].flipped();
+        let mut vm = Vm::new(program);
+        let outcome = vm.run_until(|vm| vm.steps() > 100);
+        assert_eq!(Some(Outcome::Terminated { acc: 8 }), outcome);
+    }
+
+    #[test]
+    fn test_jump_out_of_bounds() {
+        assert_eq!(
+            Ok(Outcome::JumpOutOfBounds { from: 1, target: -2 }),
+            run_boot_code("acc +1\njmp -3\nacc +1", 100)
+        );
+        assert_eq!(
+            Ok(Outcome::JumpOutOfBounds { from: 0, target: 4 }),
+            run_boot_code("jmp +4\nacc +1\nacc +1", 100)
+        );
+        assert_eq!(
+            Ok(Outcome::Terminated { acc: 1 }),
+            run_boot_code("jmp +2\nacc +1\nacc +1", 100)
+        );
+        assert_eq!(Ok(Outcome::Terminated { acc: 0 }), run_boot_code("", 100));
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        assert_eq!(Ok(Outcome::StepLimitExceeded), run_boot_code(&program, 6));
+        assert_eq!(Ok(Outcome::InfiniteLoop { acc: 5, at: 1 }), run_boot_code(&program, 7));
+    }
+
+    #[test]
+    fn test_accumulator_overflow() {
+        assert_eq!(
+            Ok(Outcome::AccumulatorOverflow { at: 1 }),
+            run_boot_code("acc +2147483647\nacc +1", 10)
+        );
+        assert_eq!(
+            Ok(Outcome::AccumulatorOverflow { at: 2 }),
+            run_boot_code("acc -2147483647\nnop +0\nacc -2", 10)
+        );
+        let mut vm = Vm::new(get_program("accb +2147483647\naccb +2147483647"));
+        assert_eq!(Outcome::AccumulatorOverflow { at: 1 }, vm.run());
+        assert_eq!((2, 2147483647), (vm.steps(), vm.state().b));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err(AssembleError {
+                line: 2,
+                kind: AssembleErrorKind::UnknownMnemonic("foo".to_string())
+            }),
+            run_boot_code("acc +1\nfoo +1", 10)
+        );
+        assert_eq!(
+            "line 1: unexpected \"acc 1\"",
+            parse_program("acc 1").unwrap_err().to_string()
+        );
+        assert_eq!(
+            Err(AssembleError {
+                line: 1,
+                kind: AssembleErrorKind::InvalidArgument("+4294967296".to_string())
+            }),
+            parse_program("jmp +4294967296")
+        );
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Extended {
+        Base(Instruction),
+        Mul(i32),
+    }
+
+    impl Operation for Extended {
+        fn execute(&self, state: &mut State) -> Flow {
+            match self {
+                Extended::Base(instruction) => instruction.execute(state),
+                Extended::Mul(num) => {
+                    state.acc *= num;
+                    Flow::Next
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_vm_custom_opcode() {
+        let program = vec![
+            Extended::Base(Acc(3)),
+            Extended::Mul(4),
+            Extended::Base(Jmp(2)),
+            Extended::Mul(0),
+            Extended::Mul(-1),
+        ];
+        assert_eq!(Outcome::Terminated { acc: -12 }, Vm::new(program).run());
+    }
+
+    #[test]
+    fn test_get_instruction_extended() {
+        assert_eq!(Jgz(-3), get_instruction("jgz -3"));
+        assert_eq!(AccB(2), get_instruction("accb +2"));
+        assert_eq!(Store(15), get_instruction("st +15"));
+        assert_eq!("jnz +4", Jnz(4).to_string());
+    }
+
+    #[test]
+    fn test_conditional_loop() {
+        let program = "acc +3\naccb +5\nacc -1\njnz -2\nswp +0";
+        let mut vm = Vm::new(get_program(program));
+        assert_eq!(Outcome::Terminated { acc: 15 }, vm.run());
+        assert_eq!((11, 3, 0), (vm.steps(), vm.visit_count(1), vm.state().b));
+        let program = "acc +1\njgz +2\njmp -2\nacc -2\njmp -4";
+        assert_eq!(Ok(Outcome::InfiniteLoop { acc: 0, at: 0 }), run_boot_code(program, 100));
+        assert_eq!(Ok(Outcome::Terminated { acc: 0 }), run_boot_code("jz +2\nacc +1", 10));
+    }
+
+    #[test]
+    fn test_memory() {
+        let program = "acc +7\nst +3\nacc -7\nld +3\nst +0\nld +0";
+        let mut vm = Vm::new(get_program(program));
+        assert_eq!(Outcome::Terminated { acc: 7 }, vm.run());
+        assert_eq!(7, vm.state().memory[3]);
+        assert_eq!(
+            Ok(Outcome::InvalidAddress { at: 1, address: 16 }),
+            run_boot_code("acc +1\nst +16", 10)
+        );
     }
 
     #[test]
@@ -225,6 +646,9 @@ mod tests {
 
     #[test]
     fn test_task2() {
-        assert_eq!(920, get_acc_at_end("data/boot_code.txt"));
+        assert_eq!(Some(920), get_acc_at_end("data/boot_code.txt"));
+        let repairs = get_repairs("data/boot_code.txt", 1, false);
+        assert_eq!(1, repairs.len());
+        assert_eq!(920, repairs[0].acc);
     }
 }
@@ -1,7 +1,61 @@
+use std::env;
+use std::fs;
+use std::io;
+
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if args.first().map(|arg| arg.as_str()) == Some("debug") {
+        let filename = args.get(1).map_or("data/boot_code.txt", |arg| arg.as_str());
+        let program = fs::read_to_string(filename).unwrap();
+        let stdin = io::stdin();
+        day_08::debugger::debug_program(&program, stdin.lock(), io::stdout()).unwrap();
+        return;
+    }
+    if args.first().map(|arg| arg.as_str()) == Some("cfg") {
+        let filename = args.get(1).map_or("data/boot_code.txt", |arg| arg.as_str());
+        let program = day_08::get_program(&fs::read_to_string(filename).unwrap());
+        print!("{}", day_08::cfg::ControlFlowGraph::new(&program).to_dot());
+        return;
+    }
+    if args.first().map(|arg| arg.as_str()) == Some("trace") {
+        let filename = args.get(3).map_or("data/boot_code.txt", |arg| arg.as_str());
+        let trace = match args.get(1).map(|arg| arg.as_str()) {
+            Some("end") => match day_08::get_trace_at_end(filename) {
+                Some(trace) => trace,
+                None => {
+                    eprintln!("No single instruction change fixes the program");
+                    return;
+                }
+            },
+            _ => day_08::get_trace_of_program(filename),
+        };
+        match args.get(2).map(|arg| arg.as_str()) {
+            Some("csv") => print!("{}", trace.to_csv()),
+            _ => print!("{}", trace.to_json_lines()),
+        }
+        eprint!("{}", trace.summary());
+        return;
+    }
+
     let acc = day_08::get_acc_of_program("data/boot_code.txt");
     println!("Acc at loop: {}", acc);
 
-    let acc = day_08::get_acc_at_end("data/boot_code.txt");
-    println!("Acc at end of changed program: {}", acc);
-}
\ No newline at end of file
+    match day_08::get_acc_at_end("data/boot_code.txt") {
+        Some(acc) => println!("Acc at end of changed program: {}", acc),
+        None => println!("No single instruction change fixes the program"),
+    }
+
+    for repair in day_08::get_repairs("data/boot_code.txt", 1, false) {
+        let patches = repair
+            .patches
+            .iter()
+            .map(|patch| patch.to_string())
+            .collect::<Vec<String>>();
+        println!(
+            "Repair [{}]: acc {} after {} steps",
+            patches.join(", "),
+            repair.acc,
+            repair.steps
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use crate::Instruction::{self, Acc, Jmp, Noop};
+use crate::{Outcome, Vm};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Patch {
+    Flip(usize),
+    Delete(usize),
+}
+
+impl Patch {
+    pub fn index(&self) -> usize {
+        match *self {
+            Patch::Flip(index) | Patch::Delete(index) => index,
+        }
+    }
+
+    pub fn apply(&self, program: &mut [Instruction]) {
+        let instruction = &mut program[self.index()];
+        *instruction = match (*self, *instruction) {
+            (Patch::Flip(_), _) => instruction.flipped(),
+            (Patch::Delete(_), Acc(num)) => Noop(num),
+            (Patch::Delete(_), other) => other,
+        };
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Patch::Flip(index) => write!(f, "flip {}", index),
+            Patch::Delete(index) => write!(f, "delete {}", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub patches: Vec<Patch>,
+    pub acc: i32,
+    pub steps: usize,
+}
+
+impl Repair {
+    pub fn apply(&self, program: &[Instruction]) -> Vec<Instruction> {
+        let mut program = program.to_vec();
+        for patch in self.patches.iter() {
+            patch.apply(&mut program);
+        }
+        program
+    }
+}
+
+pub struct RepairSearch {
+    program: Vec<Instruction>,
+    max_changes: usize,
+    deletions: bool,
+}
+
+impl RepairSearch {
+    pub fn new(program: &[Instruction]) -> RepairSearch {
+        RepairSearch {
+            program: program.to_vec(),
+            max_changes: 1,
+            deletions: false,
+        }
+    }
+
+    pub fn with_max_changes(mut self, max_changes: usize) -> RepairSearch {
+        self.max_changes = max_changes;
+        self
+    }
+
+    pub fn with_deletions(mut self, deletions: bool) -> RepairSearch {
+        self.deletions = deletions;
+        self
+    }
+
+    fn patch_at(&self, index: usize) -> Option<Patch> {
+        match self.program[index] {
+            Jmp(_) | Noop(_) => Some(Patch::Flip(index)),
+            Acc(_) if self.deletions => Some(Patch::Delete(index)),
+            _ => None,
+        }
+    }
+
+    fn explore(&self, mut vm: Vm, patches: &mut Vec<Patch>, repairs: &mut Vec<Repair>) {
+        loop {
+            if let Some(outcome) = vm.outcome() {
+                if let Outcome::Terminated { acc } = outcome {
+                    if !patches.is_empty() {
+                        let mut sorted = patches.clone();
+                        sorted.sort();
+                        repairs.push(Repair {
+                            patches: sorted,
+                            acc,
+                            steps: vm.steps(),
+                        });
+                    }
+                }
+                return;
+            }
+            if patches.len() < self.max_changes && vm.visit_count(vm.ip()) == 0 {
+                if let Some(patch) = self.patch_at(vm.ip()) {
+                    let mut patched = vm.clone();
+                    patch.apply(&mut patched.program);
+                    patches.push(patch);
+                    self.explore(patched, patches, repairs);
+                    patches.pop();
+                }
+            }
+            vm.step();
+        }
+    }
+
+    pub fn repairs(&self) -> Vec<Repair> {
+        let mut repairs = Vec::new();
+        self.explore(Vm::new(self.program.clone()), &mut Vec::new(), &mut repairs);
+        let fewest = repairs.iter().map(|repair| repair.patches.len()).min();
+        repairs.retain(|repair| Some(repair.patches.len()) == fewest);
+        repairs.sort_by(|a, b| a.patches.cmp(&b.patches));
+        repairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::get_program;
+    use crate::repair::{Patch, Repair, RepairSearch};
+    use crate::Instruction::{Acc, Noop};
+    use std::fs;
+
+    #[test]
+    fn test_single_flips() {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        let program = get_program(&program);
+        assert_eq!(
+            vec![Repair {
+                patches: vec![Patch::Flip(7)],
+                acc: 8,
+                steps: 6
+            }],
+            RepairSearch::new(&program).repairs()
+        );
+        assert_eq!(
+            RepairSearch::new(&program).repairs(),
+            RepairSearch::new(&program)
+                .with_max_changes(3)
+                .with_deletions(true)
+                .repairs()
+        );
+    }
+
+    #[test]
+    fn test_all_single_flips_reported() {
+        let program = get_program("nop +2\njmp +0\nacc +1\njmp +1");
+        let repairs = RepairSearch::new(&program).repairs();
+        assert_eq!(
+            vec![vec![Patch::Flip(0)], vec![Patch::Flip(1)]],
+            repairs
+                .iter()
+                .map(|repair| repair.patches.clone())
+                .collect::<Vec<Vec<Patch>>>()
+        );
+        assert_eq!(
+            vec![(1, 3), (1, 4)],
+            repairs
+                .iter()
+                .map(|repair| (repair.acc, repair.steps))
+                .collect::<Vec<(i32, usize)>>()
+        );
+    }
+
+    #[test]
+    fn test_no_repair() {
+        let program = get_program("jmp +0\njmp +0\nacc +1");
+        assert_eq!(Vec::<Repair>::new(), RepairSearch::new(&program).repairs());
+        assert_eq!(
+            vec![Repair {
+                patches: vec![Patch::Flip(0), Patch::Flip(1)],
+                acc: 1,
+                steps: 3
+            }],
+            RepairSearch::new(&program).with_max_changes(2).repairs()
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let program = get_program("acc +1\njmp +0");
+        let repair = Repair {
+            patches: vec![Patch::Delete(0), Patch::Flip(1)],
+            acc: 0,
+            steps: 2,
+        };
+        assert_eq!(vec![Noop(1), Noop(0)], repair.apply(&program));
+        let repair = Repair {
+            patches: vec![Patch::Flip(1)],
+            ..repair
+        };
+        assert_eq!(vec![Acc(1), Noop(0)], repair.apply(&program));
+        assert_eq!("delete 0", Patch::Delete(0).to_string());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Instruction, Outcome, Vm};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceStep {
+    pub step: usize,
+    pub ip: usize,
+    pub instruction: Instruction,
+    pub acc_before: i32,
+    pub acc_after: i32,
+    pub next_ip: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub program: Vec<Instruction>,
+    pub steps: Vec<TraceStep>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceSummary {
+    pub steps: usize,
+    pub hits: Vec<usize>,
+    pub first_repeated_ip: Option<usize>,
+    pub outcome: Outcome,
+}
+
+impl Trace {
+    pub fn record(vm: &mut Vm) -> Trace {
+        let mut steps = Vec::new();
+        let outcome = loop {
+            let (ip, acc_before, executed) = (vm.ip(), vm.acc(), vm.steps());
+            let outcome = vm.step();
+            if vm.steps() > executed {
+                let next_ip = match outcome {
+                    Some(Outcome::JumpOutOfBounds { target, .. }) => target,
+                    _ => vm.ip() as i64,
+                };
+                steps.push(TraceStep {
+                    step: vm.steps(),
+                    ip,
+                    instruction: vm.program()[ip],
+                    acc_before,
+                    acc_after: vm.acc(),
+                    next_ip,
+                });
+            }
+            if let Some(outcome) = outcome {
+                break outcome;
+            }
+        };
+        Trace {
+            program: vm.program().to_vec(),
+            steps,
+            outcome,
+        }
+    }
+
+    pub fn to_json_lines(&self) -> String {
+        self.steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "{{\"step\": {}, \"ip\": {}, \"instruction\": \"{}\", \"acc_before\": {}, \
+                     \"acc_after\": {}, \"next_ip\": {}}}\n",
+                    step.step,
+                    step.ip,
+                    step.instruction,
+                    step.acc_before,
+                    step.acc_after,
+                    step.next_ip
+                )
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,ip,instruction,acc_before,acc_after,next_ip\n");
+        for step in self.steps.iter() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                step.step, step.ip, step.instruction, step.acc_before, step.acc_after, step.next_ip
+            ));
+        }
+        csv
+    }
+
+    pub fn summary(&self) -> TraceSummary {
+        let mut hits = vec![0; self.program.len()];
+        let mut first_repeated_ip = None;
+        for step in self.steps.iter() {
+            hits[step.ip] += 1;
+            if hits[step.ip] == 2 && first_repeated_ip.is_none() {
+                first_repeated_ip = Some(step.ip);
+            }
+        }
+        // The vm stops before re-running a looping instruction, so a loop
+        // without repeats inside the trace is reported where it was detected.
+        if let (None, Outcome::InfiniteLoop { at, .. }) = (first_repeated_ip, self.outcome) {
+            first_repeated_ip = Some(at);
+        }
+        TraceSummary {
+            steps: self.steps.len(),
+            hits,
+            first_repeated_ip,
+            outcome: self.outcome,
+        }
+    }
+}
+
+impl fmt::Display for TraceSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outcome = match self.outcome {
+            Outcome::Terminated { acc } => format!("terminated with acc {}", acc),
+            Outcome::InfiniteLoop { acc, at } => {
+                format!("infinite loop at {} with acc {}", at, acc)
+            }
+            Outcome::JumpOutOfBounds { from, target } => {
+                format!("jump out of bounds from {} to {}", from, target)
+            }
+            Outcome::InvalidAddress { at, address } => {
+                format!("invalid memory address {} at {}", address, at)
+            }
+            Outcome::AccumulatorOverflow { at } => format!("accumulator overflow at {}", at),
+            Outcome::StepLimitExceeded => "step limit exceeded".to_string(),
+        };
+        writeln!(f, "outcome: {}", outcome)?;
+        writeln!(f, "steps: {}", self.steps)?;
+        match self.first_repeated_ip {
+            Some(ip) => writeln!(f, "first repeated ip: {}", ip)?,
+            None => writeln!(f, "first repeated ip: none")?,
+        }
+        let hits = self
+            .hits
+            .iter()
+            .enumerate()
+            .filter(|(_, &hits)| hits > 0)
+            .map(|(ip, hits)| format!("{}:{}", ip, hits))
+            .collect::<Vec<String>>();
+        writeln!(f, "hits: {}", hits.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trace::Trace;
+    use crate::{get_program, Outcome, Vm};
+    use std::fs;
+
+    fn example_trace() -> Trace {
+        let program = fs::read_to_string("data/example.txt").unwrap();
+        Trace::record(&mut Vm::new(get_program(&program)))
+    }
+
+    #[test]
+    fn test_record() {
+        let trace = example_trace();
+        assert_eq!(7, trace.steps.len());
+        assert_eq!(Outcome::InfiniteLoop { acc: 5, at: 1 }, trace.outcome);
+        assert_eq!(
+            vec![0, 1, 2, 6, 7, 3, 4],
+            trace.steps.iter().map(|step| step.ip).collect::<Vec<usize>>()
+        );
+        let last = trace.steps[6];
+        assert_eq!((7, 5, 5, 1), (last.step, last.acc_before, last.acc_after, last.next_ip));
+        let trace = Trace::record(&mut Vm::new(get_program("acc +2\njmp -2")));
+        assert_eq!(Outcome::JumpOutOfBounds { from: 1, target: -1 }, trace.outcome);
+        assert_eq!(-1, trace.steps[1].next_ip);
+    }
+
+    #[test]
+    fn test_export() {
+        let trace = Trace::record(&mut Vm::new(get_program("acc +2\nnop +0")));
+        assert_eq!(
+            "{\"step\": 1, \"ip\": 0, \"instruction\": \"acc +2\", \"acc_before\": 0, \
+             \"acc_after\": 2, \"next_ip\": 1}\n\
+             {\"step\": 2, \"ip\": 1, \"instruction\": \"nop +0\", \"acc_before\": 2, \
+             \"acc_after\": 2, \"next_ip\": 2}\n",
+            trace.to_json_lines()
+        );
+        assert_eq!(
+            "step,ip,instruction,acc_before,acc_after,next_ip\n\
+             1,0,acc +2,0,2,1\n\
+             2,1,nop +0,2,2,2\n",
+            trace.to_csv()
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = example_trace().summary();
+        assert_eq!(Some(1), summary.first_repeated_ip);
+        assert_eq!(vec![1, 1, 1, 1, 1, 0, 1, 1, 0], summary.hits);
+        assert_eq!(
+            "outcome: infinite loop at 1 with acc 5\n\
+             steps: 7\n\
+             first repeated ip: 1\n\
+             hits: 0:1 1:1 2:1 3:1 4:1 6:1 7:1\n",
+            summary.to_string()
+        );
+        let program = get_program("acc +3\naccb +5\nacc -1\njnz -2\nswp +0");
+        let summary = Trace::record(&mut Vm::new(program)).summary();
+        assert_eq!(Some(1), summary.first_repeated_ip);
+        assert_eq!(vec![1, 3, 3, 3, 1], summary.hits);
+        let summary = Trace::record(&mut Vm::new(get_program("acc +1\njmp +2"))).summary();
+        assert_eq!(None, summary.first_repeated_ip);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::Weakness;
+use std::fmt;
+
+const MAX_ATTEMPTS: u64 = 100;
+pub const MAX_VALUE: u64 = u64::MAX / 8;
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmasSpec {
+    pub length: usize,
+    pub count: usize,
+    pub invalid_at: Vec<usize>,
+    pub weakness: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenerateError {
+    WindowTooShort(usize),
+    InvalidPosition(usize),
+    ValueOutOfRange { index: usize },
+    NoUniqueWeakness { seed: u64 },
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::WindowTooShort(length) => {
+                write!(f, "a window needs at least two numbers, got {}", length)
+            }
+            GenerateError::InvalidPosition(index) => write!(
+                f,
+                "invalid number at index {} is not between the preamble and the end",
+                index
+            ),
+            GenerateError::ValueOutOfRange { index } => write!(
+                f,
+                "no number at index {} fits the XMAS rules below {}",
+                index, MAX_VALUE
+            ),
+            GenerateError::NoUniqueWeakness { seed } => {
+                write!(f, "could not plant a unique weakness for seed {}", seed)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenerateError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmasSample {
+    pub codes: Vec<u64>,
+    pub invalid: Vec<(usize, u64)>,
+    pub weakness: Option<Weakness>,
+}
+
+impl XmasSample {
+    pub fn to_text(&self) -> String {
+        self.codes.iter().map(|code| format!("{}\n", code)).collect()
+    }
+}
+
+fn pair_sums(window: &[u64]) -> Vec<u64> {
+    let mut sums = Vec::new();
+    for i in 0..window.len() {
+        for j in i + 1..window.len() {
+            sums.push(window[i] + window[j]);
+        }
+    }
+    sums
+}
+
+fn ranges_summing_to(codes: &[u64], target: u64) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    for start in 0..codes.len() {
+        let mut sum = codes[start];
+        for end in start + 2..=codes.len() {
+            sum += codes[end - 1];
+            if sum > target {
+                break;
+            }
+            if sum == target {
+                ranges.push((start, end));
+            }
+        }
+    }
+    ranges
+}
+
+fn plant_weakness(codes: &[u64], length: usize, rng: &mut Rng) -> Option<(usize, usize, u64)> {
+    let sums = pair_sums(&codes[codes.len() - length..]);
+    let mut candidates = Vec::new();
+    for start in 0..codes.len() {
+        for end in start + 2..=codes.len().min(start + 6) {
+            let sum = codes[start..end].iter().sum::<u64>();
+            if sum <= MAX_VALUE && !sums.contains(&sum) {
+                candidates.push((start, end, sum));
+            }
+        }
+    }
+    if candidates.is_empty() {
+        None
+    } else {
+        Some(candidates[rng.below(candidates.len() as u64) as usize])
+    }
+}
+
+fn valid_value(window: &[u64], rng: &mut Rng) -> Option<u64> {
+    let a = rng.below(window.len() as u64) as usize;
+    let b = (a + 1 + rng.below(window.len() as u64 - 1) as usize) % window.len();
+    let value = window[a] + window[b];
+    if value <= MAX_VALUE {
+        return Some(value);
+    }
+    pair_sums(window).into_iter().filter(|&sum| sum <= MAX_VALUE).min()
+}
+
+// Every valid number is larger than both of its summands, so a stream grows
+// exponentially; Err means the spec cannot fit below MAX_VALUE, Ok(None) that
+// this attempt planted an ambiguous weakness and another one may succeed.
+fn try_generate(rng: &mut Rng, spec: &XmasSpec) -> Result<Option<XmasSample>, GenerateError> {
+    let first_invalid = spec.invalid_at.iter().min().copied();
+    let mut codes = Vec::with_capacity(spec.count);
+    let mut invalid = Vec::new();
+    let mut planted = None;
+    for i in 0..spec.count {
+        if i < spec.length {
+            codes.push(1 + rng.below(50));
+            continue;
+        }
+        let window = &codes[i - spec.length..i];
+        let value = if spec.weakness && Some(i) == first_invalid {
+            let (start, end, sum) = match plant_weakness(&codes, spec.length, rng) {
+                Some(weakness) => weakness,
+                None => return Ok(None),
+            };
+            planted = Some((start, end));
+            Some(sum)
+        } else if spec.invalid_at.contains(&i) {
+            let value = pair_sums(window).into_iter().max().unwrap() + 1 + rng.below(10);
+            Some(value).filter(|&value| value <= MAX_VALUE)
+        } else {
+            valid_value(window, rng)
+        };
+        let value = value.ok_or(GenerateError::ValueOutOfRange { index: i })?;
+        if spec.invalid_at.contains(&i) {
+            invalid.push((i, value));
+        }
+        codes.push(value);
+    }
+    let weakness = match planted {
+        Some((start, end)) => {
+            if ranges_summing_to(&codes, invalid[0].1) != vec![(start, end)] {
+                return Ok(None);
+            }
+            let range = &codes[start..end];
+            Some(Weakness {
+                start,
+                end,
+                min: *range.iter().min().unwrap(),
+                max: *range.iter().max().unwrap(),
+            })
+        }
+        None => None,
+    };
+    Ok(Some(XmasSample {
+        codes,
+        invalid,
+        weakness,
+    }))
+}
+
+pub fn generate(seed: u64, spec: &XmasSpec) -> Result<XmasSample, GenerateError> {
+    if spec.length < 2 {
+        return Err(GenerateError::WindowTooShort(spec.length));
+    }
+    if let Some(&i) = spec
+        .invalid_at
+        .iter()
+        .find(|&&i| i < spec.length || i >= spec.count)
+    {
+        return Err(GenerateError::InvalidPosition(i));
+    }
+    let mut rng = Rng(seed);
+    for _ in 0..MAX_ATTEMPTS {
+        if let Some(sample) = try_generate(&mut rng, spec)? {
+            return Ok(sample);
+        }
+    }
+    Err(GenerateError::NoUniqueWeakness { seed })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::generator::{generate, GenerateError, XmasSpec, MAX_VALUE};
+    use crate::stream::{InvalidNumber, StreamError, XmasStream};
+    use crate::{find_all_invalid, find_invalid, find_weaknesses, get_min_max_sum};
+    use crate::{get_codes, get_invalid_numbers, get_weaknesses};
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+    use std::process;
+
+    fn spec(seed: u64, length: usize) -> XmasSpec {
+        let first = length + 3 + (seed % 7) as usize;
+        XmasSpec {
+            length,
+            count: length + 30,
+            invalid_at: vec![first, length + 12 + (seed % 5) as usize, length + 29],
+            weakness: true,
+        }
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let spec = spec(3, 5);
+        assert_eq!(generate(3, &spec), generate(3, &spec));
+        assert_ne!(generate(3, &spec).unwrap().codes, generate(4, &spec).unwrap().codes);
+        let sample = generate(3, &spec).unwrap();
+        assert_eq!(35, sample.codes.len());
+        assert_eq!(
+            spec.invalid_at,
+            sample.invalid.iter().map(|(i, _)| *i).collect::<Vec<usize>>()
+        );
+        assert!(sample.weakness.unwrap().end <= sample.invalid[0].0);
+    }
+
+    #[test]
+    fn test_properties() {
+        for &length in [2, 3, 5, 10, 25].iter() {
+            for seed in 0..40 {
+                let spec = spec(seed, length);
+                let sample = generate(seed, &spec).unwrap();
+                let codes = get_codes(&sample.to_text());
+                let weakness = sample.weakness.unwrap();
+                let invalid = get_invalid_numbers(&codes, length).collect::<Vec<(usize, u64)>>();
+                assert_eq!(sample.invalid, invalid);
+                assert_eq!(vec![weakness], get_weaknesses(&codes, invalid[0].1));
+                let path = env::temp_dir().join(format!(
+                    "day_09_properties_{}_{}_{}.txt",
+                    process::id(),
+                    length,
+                    seed
+                ));
+                let filename = path.to_str().unwrap();
+                fs::write(&path, sample.to_text()).unwrap();
+                assert_eq!(sample.invalid[0].1, find_invalid(filename, length));
+                assert_eq!(sample.invalid, find_all_invalid(filename, length));
+                assert_eq!(vec![weakness], find_weaknesses(filename, length));
+                assert_eq!(Some(weakness.value()), get_min_max_sum(filename, length));
+                fs::remove_file(&path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_stream() {
+        for &(length, count) in [(2, 75), (5, 140), (25, 600)].iter() {
+            for seed in 0..5 {
+                let spec = XmasSpec {
+                    length,
+                    count,
+                    invalid_at: vec![length + 7, count / 2, count - 1],
+                    weakness: false,
+                };
+                let sample = generate(seed, &spec).unwrap();
+                assert!(sample.codes.iter().all(|&code| code <= MAX_VALUE));
+                let events = XmasStream::new(Cursor::new(sample.to_text()), length)
+                    .collect::<Result<Vec<InvalidNumber>, StreamError>>()
+                    .unwrap();
+                assert_eq!(
+                    sample.invalid,
+                    events
+                        .iter()
+                        .map(|event| (event.index, event.value))
+                        .collect::<Vec<(usize, u64)>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let spec = |length, count| XmasSpec {
+            length,
+            count,
+            invalid_at: Vec::new(),
+            weakness: false,
+        };
+        assert!(matches!(
+            generate(1, &spec(2, 120)),
+            Err(GenerateError::ValueOutOfRange { .. })
+        ));
+        assert!(matches!(
+            generate(1, &spec(25, 2000)),
+            Err(GenerateError::ValueOutOfRange { .. })
+        ));
+        assert_eq!(Err(GenerateError::WindowTooShort(1)), generate(1, &spec(1, 10)));
+        let spec = XmasSpec {
+            invalid_at: vec![3],
+            ..spec(5, 10)
+        };
+        assert_eq!(Err(GenerateError::InvalidPosition(3)), generate(1, &spec));
+    }
+}
@@ -1,5 +1,74 @@
+use std::collections::{HashMap, VecDeque};
 use std::fs;
 
+pub mod generator;
+pub mod stream;
+
+pub struct XmasWindow {
+    length: usize,
+    values: VecDeque<u64>,
+    counts: HashMap<u64, usize>,
+}
+
+impl XmasWindow {
+    pub fn new(length: usize) -> XmasWindow {
+        XmasWindow {
+            length,
+            values: VecDeque::with_capacity(length + 1),
+            counts: HashMap::with_capacity(length + 1),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.length
+    }
+
+    // Looks up the complement of every distinct value in the window, so each
+    // check is O(length) rather than the O(length²) of comparing all pairs.
+    pub fn is_valid(&self, next: u64) -> bool {
+        self.counts.keys().any(|&x| {
+            x <= next
+                && match self.counts.get(&(next - x)) {
+                    Some(&count) => next - x != x || count >= 2,
+                    None => false,
+                }
+        })
+    }
+
+    pub fn push(&mut self, value: u64) -> Option<bool> {
+        let valid = if self.is_full() {
+            Some(self.is_valid(value))
+        } else {
+            None
+        };
+        self.values.push_back(value);
+        *self.counts.entry(value).or_insert(0) += 1;
+        if self.values.len() > self.length {
+            let oldest = self.values.pop_front().unwrap();
+            let count = self.counts.get_mut(&oldest).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+        valid
+    }
+}
+
+fn get_codes(text: &str) -> Vec<u64> {
+    text.lines().map(|e| e.parse().unwrap()).collect()
+}
+
+fn get_invalid_numbers(codes: &[u64], length: usize) -> impl Iterator<Item = (usize, u64)> + '_ {
+    let mut window = XmasWindow::new(length);
+    codes
+        .iter()
+        .enumerate()
+        .filter(move |(_, &code)| window.push(code) == Some(false))
+        .map(|(i, &code)| (i, code))
+}
+
+#[cfg(test)]
 fn is_valid(preamble: &[u64], next: u64) -> bool {
     let mut result = false;
     for i in 0..preamble.len() {
@@ -13,6 +82,7 @@ fn is_valid(preamble: &[u64], next: u64) -> bool {
     result
 }
 
+#[cfg(test)]
 fn get_invalid(codes: &[u64], length: usize) -> Option<u64> {
     let mut result = None;
     for i in length..codes.len() {
@@ -25,37 +95,84 @@ fn get_invalid(codes: &[u64], length: usize) -> Option<u64> {
 }
 
 pub fn find_invalid(filename: &str, length: usize) -> u64 {
-    let codes: Vec<u64> = fs::read_to_string(filename).unwrap().lines().map(|e| e.parse().unwrap()).collect();
-    get_invalid(&codes, length).unwrap()
+    let codes = get_codes(&fs::read_to_string(filename).unwrap());
+    let (_, invalid) = get_invalid_numbers(&codes, length).next().unwrap();
+    invalid
 }
 
-fn find_contiguous_sum(codes: &[u64], sum: u64) -> (usize, usize) {
-    let mut low = 0;
-    let mut high = 1;
-    let mut current_sum = codes[low];
-    while current_sum != sum {
-        if current_sum < sum {
-            current_sum += codes[high];
-            high += 1;
-        } else {
-            current_sum -= codes[low];
-            low += 1;
+pub fn find_all_invalid(filename: &str, length: usize) -> Vec<(usize, u64)> {
+    let codes = get_codes(&fs::read_to_string(filename).unwrap());
+    get_invalid_numbers(&codes, length).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weakness {
+    pub start: usize,
+    pub end: usize,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Weakness {
+    pub fn value(&self) -> u64 {
+        self.min + self.max
+    }
+}
+
+fn get_weaknesses(codes: &[u64], target: u64) -> Vec<Weakness> {
+    let mut prefix_sums = Vec::with_capacity(codes.len() + 1);
+    let mut sum: u128 = 0;
+    prefix_sums.push(sum);
+    for &code in codes.iter() {
+        sum += code as u128;
+        prefix_sums.push(sum);
+    }
+    let mut starts: HashMap<u128, Vec<usize>> = HashMap::new();
+    for (i, &prefix_sum) in prefix_sums.iter().enumerate() {
+        starts.entry(prefix_sum).or_default().push(i);
+    }
+    let mut weaknesses = Vec::new();
+    for (end, &prefix_sum) in prefix_sums.iter().enumerate().skip(2) {
+        if prefix_sum < target as u128 {
+            continue;
         }
+        if let Some(indices) = starts.get(&(prefix_sum - target as u128)) {
+            for &start in indices.iter().take_while(|&&start| start + 2 <= end) {
+                let range = &codes[start..end];
+                weaknesses.push(Weakness {
+                    start,
+                    end,
+                    min: *range.iter().min().unwrap(),
+                    max: *range.iter().max().unwrap(),
+                });
+            }
+        }
+    }
+    weaknesses.sort_by_key(|weakness| (weakness.start, weakness.end));
+    weaknesses
+}
+
+pub fn find_weaknesses(filename: &str, length: usize) -> Vec<Weakness> {
+    let codes = get_codes(&fs::read_to_string(filename).unwrap());
+    let invalid = get_invalid_numbers(&codes, length).next();
+    match invalid {
+        Some((_, invalid)) => get_weaknesses(&codes, invalid),
+        None => Vec::new(),
     }
-    (low, high)
 }
 
-pub fn get_min_max_sum(filename: &str, length: usize) -> u64 {
-    let codes: Vec<u64> = fs::read_to_string(filename).unwrap().lines().map(|e| e.parse().unwrap()).collect();
-    let invalid = get_invalid(&codes, length).unwrap();
-    let (low, high) = find_contiguous_sum(&codes, invalid);
-    codes[low..high].iter().min().unwrap() + codes[low..high].iter().max().unwrap()
+pub fn find_weakness(filename: &str, length: usize) -> Option<Weakness> {
+    find_weaknesses(filename, length).into_iter().next()
+}
+
+pub fn get_min_max_sum(filename: &str, length: usize) -> Option<u64> {
+    find_weakness(filename, length).map(|weakness| weakness.value())
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs;
-    use crate::{is_valid, find_invalid, find_contiguous_sum, get_min_max_sum};
+    use crate::{is_valid, get_invalid, get_codes, get_invalid_numbers, find_invalid, find_all_invalid, get_weaknesses, find_weakness, find_weaknesses, get_min_max_sum, Weakness, XmasWindow};
 
     #[test]
     fn test_is_valid1() {
@@ -74,6 +191,40 @@ mod tests {
         assert_eq!(127, find_invalid("data/example.txt", 5));
     }
 
+    #[test]
+    fn test_window() {
+        let mut window = XmasWindow::new(3);
+        assert_eq!(vec![None, None, None], vec![window.push(5), window.push(5), window.push(7)]);
+        assert!(window.is_full());
+        assert!(window.is_valid(10));
+        assert!(window.is_valid(12));
+        assert!(!window.is_valid(14));
+        assert_eq!(Some(true), window.push(12));
+        assert!(!window.is_valid(10));
+        assert!(window.is_valid(19));
+    }
+
+    #[test]
+    fn test_find_all_invalid() {
+        assert_eq!(vec![(14, 127)], find_all_invalid("data/example.txt", 5));
+        assert_eq!(258585477, find_all_invalid("data/code.txt", 25)[0].1);
+    }
+
+    #[test]
+    fn test_window_matches_pairwise() {
+        let codes = get_codes(&fs::read_to_string("data/code.txt").unwrap());
+        for length in 1..40 {
+            let expected = get_invalid(&codes, length);
+            assert_eq!(expected, get_invalid_numbers(&codes, length).next().map(|(_, code)| code));
+            let pairwise = (length..codes.len())
+                .filter(|&i| !is_valid(&codes[i - length..i], codes[i]))
+                .map(|i| (i, codes[i]))
+                .collect::<Vec<(usize, u64)>>();
+            let invalid = get_invalid_numbers(&codes, length).collect::<Vec<(usize, u64)>>();
+            assert_eq!(pairwise, invalid);
+        }
+    }
+
     #[test]
     fn test_is_valid2() {
         let codes: Vec<u64> = fs::read_to_string("data/code.txt").unwrap().lines().map(|e| e.parse().unwrap()).collect();
@@ -81,14 +232,42 @@ mod tests {
     }
 
     #[test]
-    fn test_find_contiguous_sum() {
+    fn test_get_weaknesses() {
         let codes: Vec<u64> = fs::read_to_string("data/example.txt").unwrap().lines().map(|e| e.parse().unwrap()).collect();
-        assert_eq!((2, 6), find_contiguous_sum(&codes, 127));
+        assert_eq!(
+            vec![Weakness { start: 2, end: 6, min: 15, max: 47 }],
+            get_weaknesses(&codes, 127)
+        );
+        assert_eq!(Vec::<Weakness>::new(), get_weaknesses(&codes, 36));
+        assert_eq!(Vec::<Weakness>::new(), get_weaknesses(&[], 0));
+        assert_eq!(
+            vec![(0, 2), (0, 3), (1, 4), (1, 5), (2, 4), (2, 5), (3, 5)],
+            get_weaknesses(&[5, 0, 0, 5, 0], 5)
+                .iter()
+                .map(|weakness| (weakness.start, weakness.end))
+                .collect::<Vec<(usize, usize)>>()
+        );
+        let weaknesses = get_weaknesses(&[3, 4, 1, 6, 7], 7);
+        assert_eq!(
+            vec![(0, 2, 7), (2, 4, 7)],
+            weaknesses
+                .iter()
+                .map(|weakness| (weakness.start, weakness.end, weakness.value()))
+                .collect::<Vec<(usize, usize, u64)>>()
+        );
+    }
+
+    #[test]
+    fn test_find_weaknesses() {
+        assert_eq!(1, find_weaknesses("data/example.txt", 5).len());
+        assert_eq!(Vec::<Weakness>::new(), find_weaknesses("data/example.txt", 1));
+        assert_eq!(None, find_weakness("data/example.txt", 1));
+        assert_eq!(Some(2), find_weakness("data/example.txt", 5).map(|weakness| weakness.start));
     }
 
     #[test]
     fn test_get_min_max_sum() {
-        assert_eq!(62, get_min_max_sum("data/example.txt", 5));
+        assert_eq!(Some(62), get_min_max_sum("data/example.txt", 5));
     }
 
     #[test]
@@ -98,6 +277,6 @@ mod tests {
 
     #[test]
     fn test_task2() {
-        assert_eq!(36981213, get_min_max_sum("data/code.txt", 25));
+        assert_eq!(Some(36981213), get_min_max_sum("data/code.txt", 25));
     }
 }
@@ -1,8 +1,52 @@
+use day_09::stream::XmasStream;
+use std::env;
+use std::io;
+use std::process;
+
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<String>>();
+    if args.first().map(|arg| arg.as_str()) == Some("stream") {
+        let parse = |index: usize, default: usize| match args.get(index) {
+            Some(arg) => arg.parse::<usize>().map_err(|_| arg.to_string()),
+            None => Ok(default),
+        };
+        let (length, history) = match (parse(1, 25), parse(2, 1000)) {
+            (Ok(length), Ok(history)) => (length, history),
+            (Err(arg), _) | (_, Err(arg)) => {
+                eprintln!("\"{}\" is not a number", arg);
+                eprintln!("usage: day_09 stream [length] [history]");
+                process::exit(2);
+            }
+        };
+        let stdin = io::stdin();
+        for event in XmasStream::new(stdin.lock(), length).with_history(history) {
+            match event {
+                Ok(invalid) => match invalid.weakness {
+                    Some(weakness) => println!(
+                        "Invalid number {} at index {}, weakness {} (indices {}..{})",
+                        invalid.value,
+                        invalid.index,
+                        weakness.value(),
+                        weakness.start,
+                        weakness.end
+                    ),
+                    None => println!("Invalid number {} at index {}", invalid.value, invalid.index),
+                },
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+        return;
+    }
+
     let filename = "data/code.txt";
     let row = day_09::find_invalid(filename, 25);
     println!("First invalid number is: {}", row);
 
-    let sum = day_09::get_min_max_sum(filename, 25);
-    println!("Weakness is: {}", sum);
-}
\ No newline at end of file
+    let invalid = day_09::find_all_invalid(filename, 25);
+    println!("Number of invalid numbers: {}", invalid.len());
+
+    match day_09::get_min_max_sum(filename, 25) {
+        Some(sum) => println!("Weakness is: {}", sum),
+        None => println!("No weakness found"),
+    }
+}
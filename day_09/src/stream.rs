@@ -0,0 +1,228 @@
+use crate::{get_weaknesses, Weakness, XmasWindow};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Parse { line: usize, text: String },
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "read error: {}", error),
+            StreamError::Parse { line, text } => {
+                write!(f, "line {}: \"{}\" is not a number", line, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> StreamError {
+        StreamError::Io(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidNumber {
+    pub index: usize,
+    pub value: u64,
+    pub weakness: Option<Weakness>,
+}
+
+pub struct XmasStream<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    index: usize,
+    window: XmasWindow,
+    history: VecDeque<u64>,
+    history_size: usize,
+    done: bool,
+}
+
+impl<R: BufRead> XmasStream<R> {
+    pub fn new(reader: R, length: usize) -> XmasStream<R> {
+        XmasStream {
+            lines: reader.lines(),
+            line: 0,
+            index: 0,
+            window: XmasWindow::new(length),
+            history: VecDeque::new(),
+            history_size: 0,
+            done: false,
+        }
+    }
+
+    pub fn with_history(mut self, history_size: usize) -> XmasStream<R> {
+        self.history_size = history_size;
+        self.history = VecDeque::with_capacity(history_size + 1);
+        self
+    }
+
+    fn weakness(&mut self, value: u64) -> Option<Weakness> {
+        let history = self.history.make_contiguous();
+        let first = self.index - history.len();
+        get_weaknesses(history, value)
+            .first()
+            .map(|weakness| Weakness {
+                start: first + weakness.start,
+                end: first + weakness.end,
+                ..*weakness
+            })
+    }
+
+    fn accept(&mut self, value: u64) -> Option<InvalidNumber> {
+        let invalid = if self.window.push(value) == Some(false) {
+            Some(InvalidNumber {
+                index: self.index,
+                value,
+                weakness: self.weakness(value),
+            })
+        } else {
+            None
+        };
+        if self.history_size > 0 {
+            self.history.push_back(value);
+            if self.history.len() > self.history_size {
+                self.history.pop_front();
+            }
+        }
+        self.index += 1;
+        invalid
+    }
+}
+
+impl<R: BufRead> Iterator for XmasStream<R> {
+    type Item = Result<InvalidNumber, StreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(StreamError::from(error)));
+                }
+            };
+            self.line += 1;
+            let text = line.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let value = match text.parse::<u64>() {
+                Ok(value) => value,
+                Err(_) => {
+                    return Some(Err(StreamError::Parse {
+                        line: self.line,
+                        text: text.to_string(),
+                    }))
+                }
+            };
+            if let Some(invalid) = self.accept(value) {
+                return Some(Ok(invalid));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stream::{InvalidNumber, StreamError, XmasStream};
+    use crate::Weakness;
+    use std::fs;
+    use std::io::{self, BufReader, Cursor, Read};
+
+    #[test]
+    fn test_stream_example() {
+        let file = fs::File::open("data/example.txt").unwrap();
+        let events = XmasStream::new(BufReader::new(file), 5)
+            .with_history(14)
+            .collect::<Result<Vec<InvalidNumber>, StreamError>>()
+            .unwrap();
+        assert_eq!(
+            vec![InvalidNumber {
+                index: 14,
+                value: 127,
+                weakness: Some(Weakness {
+                    start: 2,
+                    end: 6,
+                    min: 15,
+                    max: 47
+                })
+            }],
+            events
+        );
+        assert_eq!(62, events[0].weakness.unwrap().value());
+    }
+
+    #[test]
+    fn test_bounded_history() {
+        let file = fs::File::open("data/example.txt").unwrap();
+        let events = XmasStream::new(BufReader::new(file), 5)
+            .with_history(11)
+            .collect::<Result<Vec<InvalidNumber>, StreamError>>()
+            .unwrap();
+        assert_eq!(None, events[0].weakness);
+        let file = fs::File::open("data/example.txt").unwrap();
+        let events = XmasStream::new(BufReader::new(file), 5)
+            .collect::<Result<Vec<InvalidNumber>, StreamError>>()
+            .unwrap();
+        assert_eq!(None, events[0].weakness);
+    }
+
+    #[test]
+    fn test_stream_code() {
+        let file = fs::File::open("data/code.txt").unwrap();
+        let events = XmasStream::new(BufReader::new(file), 25)
+            .with_history(1000)
+            .collect::<Result<Vec<InvalidNumber>, StreamError>>()
+            .unwrap();
+        assert_eq!(258585477, events[0].value);
+        assert_eq!(36981213, events[0].weakness.unwrap().value());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let input = Cursor::new("1\n2\n\nthree\n3\n4\n9\n");
+        let events = XmasStream::new(input, 2).collect::<Vec<Result<InvalidNumber, StreamError>>>();
+        assert_eq!(3, events.len());
+        assert_eq!(
+            "line 4: \"three\" is not a number",
+            events[0].as_ref().unwrap_err().to_string()
+        );
+        let invalid = events[1..]
+            .iter()
+            .map(|event| event.as_ref().unwrap().index)
+            .collect::<Vec<usize>>();
+        assert_eq!(vec![3, 4], invalid);
+    }
+
+    struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk on fire"))
+        }
+    }
+
+    #[test]
+    fn test_io_error_ends_stream() {
+        let events = XmasStream::new(BufReader::new(Broken), 2)
+            .collect::<Vec<Result<InvalidNumber, StreamError>>>();
+        assert_eq!(1, events.len());
+        assert_eq!("read error: disk on fire", events[0].as_ref().unwrap_err().to_string());
+        let input = Cursor::new("1\n2\n4\n").chain(Broken);
+        let mut stream = XmasStream::new(BufReader::new(input), 2);
+        assert_eq!(2, stream.next().unwrap().unwrap().index);
+        assert!(matches!(stream.next(), Some(Err(StreamError::Io(_)))));
+        assert!(stream.next().is_none());
+    }
+}
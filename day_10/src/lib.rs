@@ -1,87 +1,228 @@
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
 
-fn get_nr_differences(filename: &str) -> (u32, u32, u32) {
-    let mut adapters: Vec<u32> = fs::read_to_string(filename).unwrap().lines().map(|e| e.parse().unwrap()).collect();
+pub const DEFAULT_TOLERANCE: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoltError {
+    Duplicate(u32),
+    GapTooLarge { from: u32, to: u32, tolerance: u32 },
+    Overflow,
+    ZeroTolerance,
+    DeviceOutOfRange { highest: u32, tolerance: u32 },
+}
+
+impl fmt::Display for JoltError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JoltError::Duplicate(jolt) => write!(f, "more than one adapter rated {} jolts", jolt),
+            JoltError::GapTooLarge {
+                from,
+                to,
+                tolerance,
+            } => write!(
+                f,
+                "gap from {} to {} jolts is larger than the tolerance of {}",
+                from, to, tolerance
+            ),
+            JoltError::Overflow => write!(f, "result does not fit in 64 bits"),
+            JoltError::ZeroTolerance => write!(f, "tolerance must be at least 1 jolt"),
+            JoltError::DeviceOutOfRange { highest, tolerance } => write!(
+                f,
+                "device rating of {} plus {} jolts does not fit in 32 bits",
+                highest, tolerance
+            ),
+        }
+    }
+}
+
+impl std::error::Error for JoltError {}
+
+fn get_adapters(text: &str, tolerance: u32) -> Result<Vec<u32>, JoltError> {
+    if tolerance == 0 {
+        return Err(JoltError::ZeroTolerance);
+    }
+    let mut adapters: Vec<u32> = text.lines().map(|e| e.parse().unwrap()).collect();
     adapters.push(0);
-    adapters.sort();
-    let mut result = (0, 0, 0);
-    let mut prev = &adapters[0];
-    let clos = |diff: u32, result: &mut (u32, u32, u32)| {
-        match diff {
-            1 => result.0 += 1,
-            2 => result.1 += 1,
-            3 => result.2 += 1,
-            _ => panic!(),
+    adapters.sort_unstable();
+    let highest = adapters[adapters.len() - 1];
+    let device = highest
+        .checked_add(tolerance)
+        .ok_or(JoltError::DeviceOutOfRange { highest, tolerance })?;
+    adapters.push(device);
+    Ok(adapters)
+}
+
+fn check_chain(adapters: &[u32], tolerance: u32) -> Result<(), JoltError> {
+    for pair in adapters.windows(2) {
+        let diff = pair[1] - pair[0];
+        if diff == 0 {
+            return Err(JoltError::Duplicate(pair[0]));
+        }
+        if diff > tolerance {
+            return Err(JoltError::GapTooLarge {
+                from: pair[0],
+                to: pair[1],
+                tolerance,
+            });
         }
-    };
-    for cur in adapters.iter().skip(1) {
-        clos(cur - prev, &mut result);
-        prev = cur;
     }
-    adapters.push(adapters.last().unwrap() + 3);
-    let last = adapters.len() - 1;
-    clos(adapters[last] - adapters[last - 1], &mut result);
-    result
+    Ok(())
+}
+
+fn get_differences(adapters: &[u32], tolerance: u32) -> Result<BTreeMap<u32, u32>, JoltError> {
+    check_chain(adapters, tolerance)?;
+    let mut histogram = BTreeMap::new();
+    for pair in adapters.windows(2) {
+        *histogram.entry(pair[1] - pair[0]).or_insert(0) += 1;
+    }
+    Ok(histogram)
 }
 
-pub fn get_product(filename: &str) -> u32 {
-    let (one, _, three) = get_nr_differences(filename);
-    one * three
+pub fn get_difference_histogram(
+    filename: &str,
+    tolerance: u32,
+) -> Result<BTreeMap<u32, u32>, JoltError> {
+    let text = fs::read_to_string(filename).unwrap();
+    get_differences(&get_adapters(&text, tolerance)?, tolerance)
 }
 
-fn traverse_arrangements(adapters: &Vec<u32>, current: usize, known: &mut Vec<Option<u64>>) -> u64 {
+pub fn get_product(filename: &str, tolerance: u32) -> Result<u32, JoltError> {
+    let histogram = get_difference_histogram(filename, tolerance)?;
+    let count = |diff| histogram.get(&diff).copied().unwrap_or(0);
+    count(1).checked_mul(count(tolerance)).ok_or(JoltError::Overflow)
+}
+
+fn traverse_arrangements(
+    adapters: &[u32],
+    current: usize,
+    tolerance: u32,
+    known: &mut Vec<Option<u64>>,
+) -> Result<u64, JoltError> {
     if current == adapters.len() - 1 {
-        1
+        Ok(1)
     } else {
-        let mut sum = 0;
+        let mut sum: u64 = 0;
         let cur_jolt = adapters[current];
-        for (i, next_jolt) in adapters.iter().enumerate().skip(current + 1).take(3) {
-            let diff = next_jolt - cur_jolt;
-            if diff > 0 && diff < 4 {
-                if let Some(num) = known[i] {
-                    sum += num;
-                } else {
-                    let res = traverse_arrangements(adapters, i, known);
+        for (i, next_jolt) in adapters.iter().enumerate().skip(current + 1) {
+            if next_jolt - cur_jolt > tolerance {
+                break;
+            }
+            let num = match known[i] {
+                Some(num) => num,
+                None => {
+                    let res = traverse_arrangements(adapters, i, tolerance, known)?;
                     known[i] = Some(res);
-                    sum += res;
+                    res
                 }
-            }
+            };
+            sum = sum.checked_add(num).ok_or(JoltError::Overflow)?;
         }
-        sum
+        Ok(sum)
     }
 }
 
-pub fn get_arrangements(filename: &str) -> u64 {
-    let mut adapters: Vec<u32> = fs::read_to_string(filename).unwrap().lines().map(|e| e.parse().unwrap()).collect();
-    adapters.push(0);
-    adapters.sort();
-    let i = adapters.len() - 1;
-    adapters.push(adapters[i] + 3);
+fn count_arrangements(adapters: &[u32], tolerance: u32) -> Result<u64, JoltError> {
+    check_chain(adapters, tolerance)?;
     let mut known: Vec<Option<u64>> = (0..adapters.len() as u64).map(|_| None).collect();
-    traverse_arrangements(&adapters, 0, &mut known)
+    traverse_arrangements(adapters, 0, tolerance, &mut known)
+}
+
+pub fn get_arrangements(filename: &str, tolerance: u32) -> Result<u64, JoltError> {
+    let text = fs::read_to_string(filename).unwrap();
+    count_arrangements(&get_adapters(&text, tolerance)?, tolerance)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{get_nr_differences, get_arrangements, get_product};
+    use crate::{count_arrangements, get_adapters, get_arrangements, get_difference_histogram, get_differences, get_product, traverse_arrangements, JoltError, DEFAULT_TOLERANCE};
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_nr_differences() {
-        assert_eq!((22, 0, 10), get_nr_differences("data/example.txt"));
+        let histogram = vec![(1, 22), (3, 10)].into_iter().collect::<BTreeMap<u32, u32>>();
+        assert_eq!(Ok(histogram), get_difference_histogram("data/example.txt", DEFAULT_TOLERANCE));
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let adapters = get_adapters("1\n5\n7\n11", 4).unwrap();
+        assert_eq!(vec![0, 1, 5, 7, 11, 15], adapters);
+        let histogram = vec![(1, 1), (2, 1), (4, 3)].into_iter().collect::<BTreeMap<u32, u32>>();
+        assert_eq!(Ok(histogram), get_differences(&adapters, 4));
+        assert_eq!(
+            Err(JoltError::GapTooLarge { from: 1, to: 5, tolerance: 3 }),
+            get_differences(&adapters, 3)
+        );
+        assert_eq!(
+            Err(JoltError::GapTooLarge { from: 4, to: 7, tolerance: 1 }),
+            get_arrangements("data/example.txt", 1)
+        );
+        let arrangements = |tolerance| {
+            let adapters = get_adapters("1\n2\n3\n4", tolerance).unwrap();
+            traverse_arrangements(&adapters, 0, tolerance, &mut vec![None; adapters.len()])
+        };
+        assert_eq!(Ok(7), arrangements(3));
+        assert_eq!(Ok(8), arrangements(4));
+    }
+
+    #[test]
+    fn test_invalid_chains() {
+        let adapters = get_adapters("1\n2\n2\n3", DEFAULT_TOLERANCE).unwrap();
+        assert_eq!(Err(JoltError::Duplicate(2)), get_differences(&adapters, DEFAULT_TOLERANCE));
+        assert_eq!(
+            "gap from 1 to 5 jolts is larger than the tolerance of 3",
+            JoltError::GapTooLarge { from: 1, to: 5, tolerance: 3 }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let text = (1..=150).map(|jolt| jolt.to_string()).collect::<Vec<String>>().join("\n");
+        let adapters = get_adapters(&text, 5).unwrap();
+        assert_eq!(Err(JoltError::Overflow), count_arrangements(&adapters, 5));
+        let text = (1..=20).map(|jolt| jolt.to_string()).collect::<Vec<String>>().join("\n");
+        assert_eq!(Ok(121_415), count_arrangements(&get_adapters(&text, 3).unwrap(), 3));
+        assert_eq!("result does not fit in 64 bits", JoltError::Overflow.to_string());
+    }
+
+    #[test]
+    fn test_invalid_tolerance() {
+        assert_eq!(Err(JoltError::ZeroTolerance), get_adapters("1\n2", 0));
+        assert_eq!(Err(JoltError::ZeroTolerance), get_product("data/example.txt", 0));
+        assert_eq!(
+            Err(JoltError::DeviceOutOfRange { highest: 49, tolerance: u32::MAX }),
+            get_product("data/example.txt", u32::MAX)
+        );
+        assert_eq!(Ok(vec![0, 1, u32::MAX]), get_adapters("1", u32::MAX - 1));
+        assert_eq!(
+            "device rating of 49 plus 4294967295 jolts does not fit in 32 bits",
+            JoltError::DeviceOutOfRange { highest: 49, tolerance: u32::MAX }.to_string()
+        );
+    }
+
+    #[test]
+    fn test_product_tolerance() {
+        assert_eq!(Ok(22 * 10), get_product("data/example.txt", DEFAULT_TOLERANCE));
+        assert_eq!(Ok(22), get_product("data/example.txt", 4));
     }
 
     #[test]
     fn test_arrangements() {
-        assert_eq!(19208, get_arrangements("data/example.txt"));
+        assert_eq!(Ok(19208), get_arrangements("data/example.txt", DEFAULT_TOLERANCE));
     }
 
     #[test]
     fn test_task1() {
-        assert_eq!(2482, get_product("data/adapters.txt"));
+        assert_eq!(Ok(2482), get_product("data/adapters.txt", DEFAULT_TOLERANCE));
     }
 
     #[test]
     fn test_task2() {
-        assert_eq!(96_717_311_574_016, get_arrangements("data/adapters.txt"));
+        assert_eq!(
+            Ok(96_717_311_574_016),
+            get_arrangements("data/adapters.txt", DEFAULT_TOLERANCE)
+        );
     }
 }
@@ -1,8 +1,21 @@
 fn main() {
     let filename = "data/adapters.txt";
-    let product = day_10::get_product(filename);
-    println!("The distribution is: {}", product);
+    match day_10::get_product(filename, day_10::DEFAULT_TOLERANCE) {
+        Ok(product) => println!("The distribution is: {}", product),
+        Err(error) => eprintln!("Invalid adapter chain: {}", error),
+    }
 
-    let arrangements = day_10::get_arrangements(filename);
-    println!("Nr of arrangements: {}", arrangements);
-}
\ No newline at end of file
+    match day_10::get_difference_histogram(filename, day_10::DEFAULT_TOLERANCE) {
+        Ok(histogram) => {
+            for (diff, count) in histogram {
+                println!("Differences of {} jolts: {}", diff, count);
+            }
+        }
+        Err(error) => eprintln!("Invalid adapter chain: {}", error),
+    }
+
+    match day_10::get_arrangements(filename, day_10::DEFAULT_TOLERANCE) {
+        Ok(arrangements) => println!("Nr of arrangements: {}", arrangements),
+        Err(error) => eprintln!("Invalid adapter chain: {}", error),
+    }
+}